@@ -11,3 +11,7 @@ pub fn set_app_handle(handle: AppHandle) {
 pub fn get_app_handle() -> &'static AppHandle {
     APP_HANDLE.get().expect("AppHandle no ha sido inicializado")
 }
+
+pub fn try_get_app_handle() -> Option<&'static AppHandle> {
+    APP_HANDLE.get()
+}
@@ -14,8 +14,8 @@ use crate::{
         handler::{get_app_handle, set_app_handle},
         resource::ResourceManager,
     },
-    config::{self, watchdog::ConfigWatchdog},
-    engine::executor::Executor,
+    config::{self, path::init_config_path, watchdog::ConfigWatchdog},
+    engine::{self, executor::Executor, run_events::init_run_events},
     input::{self, global::init_event_channel},
 };
 
@@ -42,6 +42,49 @@ impl AppLauncher {
                 config::commands::load_config,
                 config::commands::save_config,
                 config::commands::change_active_profile,
+                config::commands::get_config_recovery,
+                config::commands::reload_config,
+                config::commands::discard_corrupt_config,
+                config::commands::get_config_snapshot,
+                config::commands::create_profile,
+                config::commands::update_profile,
+                config::commands::set_persist_variables,
+                config::commands::delete_profile,
+                config::commands::reorder_profiles,
+                config::commands::create_macro,
+                config::commands::update_macro,
+                config::commands::set_macro_script,
+                config::commands::delete_macro,
+                config::commands::reorder_macros,
+                config::commands::create_library_macro,
+                config::commands::reorder_library,
+                config::commands::move_macro,
+                config::commands::duplicate_macro,
+                config::commands::create_step,
+                config::commands::update_step,
+                config::commands::delete_step,
+                config::commands::reorder_steps,
+                config::commands::undo,
+                config::commands::redo,
+                config::commands::get_history,
+                config::commands::list_backups,
+                config::commands::restore_backup,
+                config::commands::export_profile,
+                config::commands::export_macro,
+                config::commands::import_shared,
+                config::commands::set_keyboard_layout,
+                config::commands::validate_config,
+                engine::commands::get_variables,
+                engine::commands::reset_variables,
+                engine::commands::get_run_history,
+                engine::commands::get_latency_stats,
+                engine::commands::export_run_history,
+                engine::commands::preview_macro,
+                engine::commands::simulate_macro,
+                engine::commands::run_macro,
+                engine::commands::run_sequence,
+                engine::commands::cancel_run,
+                engine::commands::get_run_status,
                 input::commands::send_keydown_event,
             ])
             .run(ctx)
@@ -81,10 +124,8 @@ impl AppLauncher {
                 api.prevent_close();
                 let _ = window.hide();
             }
-            WindowEvent::Resized(_) => {
-                if window.is_minimized().unwrap_or(false) {
-                    let _ = window.hide();
-                }
+            WindowEvent::Resized(_) if window.is_minimized().unwrap_or(false) => {
+                let _ = window.hide();
             }
             _ => {}
         }
@@ -128,6 +169,10 @@ impl AppLauncher {
     fn setup_app(app: &mut App) -> Result<()> {
         init_event_channel();
         let handle = get_app_handle().clone();
+        init_run_events(handle.clone());
+        let config_path =
+            init_config_path(&handle).context("Error al resolver la ruta de configuración")?;
+        info!("Usando configuración en {}", config_path.display());
         let executor = Arc::new(Executor::new().context("Error al iniciar el ejecutor")?);
 
         let mut resource_manager = ResourceManager::new(executor.clone())?;
@@ -142,7 +187,12 @@ impl AppLauncher {
             .watchdogs
             .set_config_watchdog(config_watchdog);
 
+        let queue_handler = resource_manager
+            .queue_handler()
+            .context("QueueHandler no inicializado")?;
+
         app.manage(executor);
+        app.manage(queue_handler);
         app.manage(Arc::new(std::sync::Mutex::new(resource_manager)));
 
         let resource_manager = app.state::<Arc<std::sync::Mutex<ResourceManager>>>();
@@ -8,6 +8,7 @@ use tracing::error;
 use crate::{
     application::watchdog::WatchdogManager,
     engine::{executor::Executor, handler::QueueHandler, processor::EventProcessor},
+    input::state::KEY_STATE,
 };
 
 #[derive(Debug)]
@@ -42,7 +43,8 @@ impl ResourceManager {
             .clone()
             .context("QueueHandler no inicializado")?;
 
-        let processor = EventProcessor::new(queue_handler, app_handle.clone())?;
+        let processor =
+            EventProcessor::new(queue_handler, Arc::clone(&KEY_STATE), app_handle.clone())?;
 
         // Spawn event loop thread
         let processor_clone = processor.clone();
@@ -69,6 +71,11 @@ impl ResourceManager {
         Ok(())
     }
 
+    #[inline]
+    pub fn queue_handler(&self) -> Option<QueueHandler> {
+        self.queue_handler.clone()
+    }
+
     pub fn shutdown(&self) {
         let _ = self.shutdown_tx.send(());
         self.executor.shutdown();
@@ -0,0 +1,333 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use tracing::{error, info, warn};
+
+use crate::{
+    config::{
+        format::ConfigFormat,
+        handler::{leave_recovery, now_ms, save_to_disk, CONFIG},
+        history,
+        model::FlatStorage,
+        parser,
+        path::config_path,
+        validation,
+    },
+    domain::{backup::BackupInfo, config::Revisioned},
+};
+
+const BACKUP_DIR: &str = "backups";
+const BACKUP_PREFIX: &str = "config-";
+
+/// Como mucho una copia automática por intervalo.
+const BACKUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const MAX_BACKUPS: usize = 20;
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+fn backup_dir() -> PathBuf {
+    config_path()
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(BACKUP_DIR)
+}
+
+/// Copia el archivo actual si la última copia es más antigua que el intervalo.
+pub(crate) fn maybe_backup() {
+    if is_due(&list().unwrap_or_default(), now_ms()) {
+        if let Err(e) = create_backup() {
+            error!("No se pudo crear copia de seguridad: {e:?}");
+        }
+    }
+}
+
+/// `true` si no hay copias o la más reciente (la primera) tiene al menos
+/// [`BACKUP_INTERVAL`].
+fn is_due(backups: &[BackupInfo], now: u64) -> bool {
+    backups
+        .first()
+        .is_none_or(|b| now.saturating_sub(b.timestamp) >= BACKUP_INTERVAL.as_millis() as u64)
+}
+
+/// Copia el archivo de configuración actual y aplica la retención.
+fn create_backup() -> Result<Option<PathBuf>> {
+    let source = config_path();
+    if !source.is_file() {
+        return Ok(None);
+    }
+
+    let dir = backup_dir();
+    fs::create_dir_all(&dir).with_context(|| format!("No se pudo crear {}", dir.display()))?;
+
+    // Misma extensión que el original para poder leerla con su formato
+    let extension = ConfigFormat::from_path(source).extension();
+    let target = dir.join(format!("{BACKUP_PREFIX}{}.{extension}", now_ms()));
+    fs::copy(source, &target)
+        .with_context(|| format!("No se pudo copiar a {}", target.display()))?;
+    info!("Copia de seguridad creada en {}", target.display());
+
+    prune(&dir, now_ms());
+    Ok(Some(target))
+}
+
+/// Borra copias por encima del límite o demasiado antiguas; la más reciente se conserva siempre.
+fn prune(dir: &Path, now: u64) {
+    let Ok(backups) = list_in(dir) else {
+        return;
+    };
+
+    for (i, backup) in backups.iter().enumerate().skip(1) {
+        let too_old = now.saturating_sub(backup.timestamp) > MAX_AGE.as_millis() as u64;
+        if i >= MAX_BACKUPS || too_old {
+            if let Err(e) = fs::remove_file(dir.join(&backup.file_name)) {
+                warn!("No se pudo borrar la copia {}: {e}", backup.file_name);
+            }
+        }
+    }
+}
+
+/// Copias disponibles, de la más reciente a la más antigua.
+pub fn list() -> Result<Vec<BackupInfo>> {
+    list_in(&backup_dir())
+}
+
+fn list_in(dir: &Path) -> Result<Vec<BackupInfo>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("No se pudo leer {}", dir.display())),
+    };
+
+    let mut backups = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let timestamp = parse_timestamp(&file_name)?;
+            let size = entry.metadata().map(|m| m.len()).unwrap_or_default();
+            Some(BackupInfo {
+                file_name,
+                timestamp,
+                size,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    backups.sort_unstable_by_key(|b| std::cmp::Reverse(b.timestamp));
+    Ok(backups)
+}
+
+#[inline]
+fn parse_timestamp(file_name: &str) -> Option<u64> {
+    let (timestamp, extension) = file_name.strip_prefix(BACKUP_PREFIX)?.split_once('.')?;
+    if ConfigFormat::from_path(Path::new(file_name)).extension() != extension {
+        return None;
+    }
+    timestamp.parse().ok()
+}
+
+/// Lee una copia de `dir` y la rechaza si no pasa [`validation::validate`].
+fn read_backup(dir: &Path, file_name: &str) -> Result<FlatStorage> {
+    // El nombre debe ser exactamente `config-<timestamp>.<ext>`, sin rutas
+    if parse_timestamp(file_name).is_none() {
+        bail!("Nombre de copia inválido: {file_name}");
+    }
+
+    let path = dir.join(file_name);
+    let bytes = fs::read(&path).with_context(|| format!("No se pudo leer {}", path.display()))?;
+    let cfg = ConfigFormat::from_path(&path)
+        .parse(&bytes)
+        .with_context(|| format!("La copia {file_name} no es una configuración válida"))?;
+    let restored = parser::config_to_flat(&cfg);
+
+    // Referencias rotas o ciclos no deben llegar a sustituir la configuración en uso
+    let issues = validation::validate(&restored);
+    if !issues.is_empty() {
+        let details = issues
+            .iter()
+            .map(|i| format!("macro {}: {}", i.macro_id, i.message))
+            .collect::<Vec<_>>()
+            .join("; ");
+        bail!("La copia {file_name} no supera la validación: {details}");
+    }
+
+    Ok(restored)
+}
+
+/// Sustituye la configuración por una copia, solo si la copia se lee y pasa
+/// [`validation::validate`].
+///
+/// También sirve para salir del modo recuperación.
+pub fn restore(file_name: &str) -> Result<Revisioned<()>> {
+    let mut restored = read_backup(&backup_dir(), file_name)?;
+
+    let mut flat = CONFIG.write();
+
+    // El estado actual también se conserva, por si la restauración fue un error
+    if let Err(e) = create_backup() {
+        warn!("No se pudo respaldar la configuración actual: {e:?}");
+    }
+
+    restored.revision = flat.revision + 1;
+    restored.next_id = restored.next_id.max(flat.next_id);
+    save_to_disk(&restored)?;
+    let recorded = history::record("Restaurar copia de seguridad", &flat, &restored);
+    *flat = restored;
+    let revision = flat.revision;
+    drop(flat);
+    if recorded {
+        history::persist();
+    }
+
+    leave_recovery();
+    info!("Configuración restaurada desde {file_name}");
+
+    Ok(Revisioned { revision, data: () })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        config::{Config, CONFIG_VERSION},
+        macros::{KeyCombination, Macro},
+        sequence_step::SequenceStep,
+    };
+
+    const MINUTE_MS: u64 = 60 * 1000;
+    const DAY_MS: u64 = 24 * 60 * MINUTE_MS;
+
+    fn info(timestamp: u64) -> BackupInfo {
+        BackupInfo {
+            file_name: format!("{BACKUP_PREFIX}{timestamp}.json"),
+            timestamp,
+            size: 0,
+        }
+    }
+
+    fn write_backups(dir: &Path, timestamps: impl IntoIterator<Item = u64>) {
+        for ts in timestamps {
+            fs::write(dir.join(format!("{BACKUP_PREFIX}{ts}.json")), "{}").unwrap();
+        }
+    }
+
+    fn remaining(dir: &Path) -> Vec<u64> {
+        list_in(dir)
+            .unwrap()
+            .into_iter()
+            .map(|b| b.timestamp)
+            .collect()
+    }
+
+    /// Configuración con una macro de biblioteca que llama a `target`.
+    fn config_calling(target: u64) -> Config {
+        Config {
+            library: vec![Macro {
+                id: 1,
+                name: "combo".into(),
+                trigger: KeyCombination::default(),
+                sequence: vec![SequenceStep::CallMacro {
+                    id: 2,
+                    macro_id: target,
+                }],
+                script: None,
+            }],
+            version: CONFIG_VERSION,
+            next_id: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn backup_is_due_after_the_interval() {
+        let now = 1_000 * DAY_MS;
+        assert!(is_due(&[], now));
+        assert!(!is_due(&[info(now - 9 * MINUTE_MS)], now));
+        assert!(is_due(&[info(now - 10 * MINUTE_MS)], now));
+        // Solo cuenta la más reciente
+        assert!(!is_due(&[info(now - MINUTE_MS), info(now - DAY_MS)], now));
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_twenty() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = 1_000 * DAY_MS;
+        write_backups(dir.path(), (0..25).map(|i| now - i * MINUTE_MS));
+        fs::write(dir.path().join("notas.txt"), "").unwrap();
+
+        prune(dir.path(), now);
+
+        let expected = (0..MAX_BACKUPS as u64)
+            .map(|i| now - i * MINUTE_MS)
+            .collect::<Vec<_>>();
+        assert_eq!(remaining(dir.path()), expected);
+        assert!(dir.path().join("notas.txt").exists());
+    }
+
+    #[test]
+    fn prune_drops_backups_older_than_thirty_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = 1_000 * DAY_MS;
+        write_backups(
+            dir.path(),
+            [now - DAY_MS, now - 30 * DAY_MS, now - 31 * DAY_MS],
+        );
+
+        prune(dir.path(), now);
+        assert_eq!(remaining(dir.path()), vec![now - DAY_MS, now - 30 * DAY_MS]);
+    }
+
+    #[test]
+    fn prune_always_keeps_the_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = 1_000 * DAY_MS;
+        write_backups(dir.path(), [now - 40 * DAY_MS, now - 50 * DAY_MS]);
+
+        prune(dir.path(), now);
+        assert_eq!(remaining(dir.path()), vec![now - 40 * DAY_MS]);
+    }
+
+    #[test]
+    fn read_backup_accepts_a_valid_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = format!("{BACKUP_PREFIX}1.json");
+        let mut cfg = config_calling(5);
+        cfg.library.push(Macro {
+            id: 5,
+            name: "objetivo".into(),
+            trigger: KeyCombination::default(),
+            sequence: Vec::new(),
+            script: None,
+        });
+        fs::write(dir.path().join(&name), serde_json::to_vec(&cfg).unwrap()).unwrap();
+
+        let flat = read_backup(dir.path(), &name).unwrap();
+        assert!(flat.macros.contains_key(&5));
+    }
+
+    #[test]
+    fn read_backup_refuses_an_invalid_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = format!("{BACKUP_PREFIX}1.json");
+        fs::write(
+            dir.path().join(&name),
+            serde_json::to_vec(&config_calling(99)).unwrap(),
+        )
+        .unwrap();
+
+        let err = read_backup(dir.path(), &name).unwrap_err().to_string();
+        assert!(err.contains("no supera la validación"), "{err}");
+        assert!(err.contains("99"), "{err}");
+    }
+
+    #[test]
+    fn read_backup_refuses_other_file_names() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("config.json"), "{}").unwrap();
+
+        assert!(read_backup(dir.path(), "config.json").is_err());
+        assert!(read_backup(dir.path(), "../config-1.json").is_err());
+    }
+}
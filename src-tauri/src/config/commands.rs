@@ -1,6 +1,20 @@
+use std::path::Path;
+
+use anyhow::Context;
+
 use crate::{
-    config::{handler, parser},
-    domain::config::Config,
+    config::{backup, handler, history, parser, share, validation},
+    domain::{
+        backup::BackupInfo,
+        config::{Config, ConfigRecovery, Revisioned},
+        enums::KeyboardLayout,
+        history::{HistoryEntry, HistoryLog},
+        macros::{KeyCombination, Macro},
+        profiles::Profile,
+        sequence_step::SequenceStep,
+        share::ImportReport,
+        validation::ValidationIssue,
+    },
 };
 
 #[tauri::command]
@@ -11,11 +25,24 @@ pub async fn load_config() -> Result<Config, String> {
     Ok(cfg)
 }
 
+/// Igual que `load_config` pero incluye la revisión para los comandos granulares.
+#[tauri::command]
+pub async fn get_config_snapshot() -> Result<Revisioned<Config>, String> {
+    let store = handler::get_config();
+
+    Ok(Revisioned {
+        revision: store.revision,
+        data: parser::flat_to_config(&store),
+    })
+}
+
 #[tauri::command]
 pub fn save_config(config: Config) -> Result<(), String> {
-    let flat = parser::config_to_flat(&config);
+    let mut flat = parser::config_to_flat(&config);
 
-    handler::save_config(|cfg| {
+    handler::save_config("Guardar configuración", |cfg| {
+        // El contador de IDs nunca retrocede aunque el cliente no lo envíe
+        flat.next_id = flat.next_id.max(cfg.next_id);
         *cfg = flat;
     })
     .map_err(|e| e.to_string())
@@ -23,8 +50,276 @@ pub fn save_config(config: Config) -> Result<(), String> {
 
 #[tauri::command]
 pub fn change_active_profile(id: u64) -> Result<(), String> {
-    handler::save_config(|cfg| {
+    handler::save_config("Cambiar perfil activo", |cfg| {
         cfg.selected_profile_id = Some(id);
     })
     .map_err(|err| err.to_string())
 }
+
+#[tauri::command]
+pub fn get_config_recovery() -> Option<ConfigRecovery> {
+    handler::get_recovery()
+}
+
+#[tauri::command]
+pub fn reload_config() -> Result<(), String> {
+    handler::reload().map_err(|e| format!("{e:#}"))
+}
+
+#[tauri::command]
+pub fn discard_corrupt_config() {
+    handler::discard_corrupt()
+}
+
+#[tauri::command]
+pub fn create_profile(
+    revision: u64,
+    name: String,
+    function_key: Option<String>,
+) -> Result<Revisioned<Profile>, String> {
+    handler::mutate_config(revision, "Crear perfil", |cfg| {
+        let id = cfg.create_profile(name, function_key);
+        Ok(parser::stored_to_profile(cfg, &cfg.profiles[&id]))
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_profile(
+    revision: u64,
+    id: u64,
+    name: String,
+    function_key: Option<String>,
+) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Editar perfil", |cfg| {
+        cfg.update_profile(id, name, function_key)
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_persist_variables(
+    revision: u64,
+    id: u64,
+    persist: bool,
+) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Cambiar persistencia de variables", |cfg| {
+        cfg.set_persist_variables(id, persist)
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_profile(revision: u64, id: u64) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Eliminar perfil", |cfg| cfg.delete_profile(id))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn reorder_profiles(revision: u64, profile_ids: Vec<u64>) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Reordenar perfiles", |cfg| {
+        cfg.reorder_profiles(profile_ids)
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_macro(
+    revision: u64,
+    profile_id: u64,
+    name: String,
+    trigger: KeyCombination,
+    sequence: Vec<SequenceStep>,
+) -> Result<Revisioned<Macro>, String> {
+    handler::mutate_config(revision, "Crear macro", |cfg| {
+        let id = cfg.create_macro(profile_id, name, trigger, sequence)?;
+        Ok(parser::stored_to_macro(cfg, &cfg.macros[&id]))
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_macro(
+    revision: u64,
+    id: u64,
+    name: String,
+    trigger: KeyCombination,
+) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Editar macro", |cfg| {
+        cfg.update_macro(id, name, trigger)
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_macro_script(
+    revision: u64,
+    id: u64,
+    script: Option<String>,
+) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Editar script de macro", |cfg| {
+        cfg.set_macro_script(id, script)
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_macro(revision: u64, id: u64) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Eliminar macro", |cfg| cfg.delete_macro(id))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn reorder_macros(
+    revision: u64,
+    profile_id: u64,
+    macro_ids: Vec<u64>,
+) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Reordenar macros", |cfg| {
+        cfg.reorder_macros(profile_id, macro_ids)
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_library_macro(
+    revision: u64,
+    name: String,
+    sequence: Vec<SequenceStep>,
+) -> Result<Revisioned<Macro>, String> {
+    handler::mutate_config(revision, "Crear macro en la biblioteca", |cfg| {
+        let id = cfg.create_library_macro(name, sequence)?;
+        Ok(parser::stored_to_macro(cfg, &cfg.macros[&id]))
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn reorder_library(revision: u64, macro_ids: Vec<u64>) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Reordenar biblioteca", |cfg| {
+        cfg.reorder_library(macro_ids)
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn move_macro(
+    revision: u64,
+    id: u64,
+    target_profile_id: u64,
+    index: Option<usize>,
+) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Mover macro", |cfg| {
+        cfg.move_macro(id, target_profile_id, index)
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn duplicate_macro(revision: u64, id: u64) -> Result<Revisioned<Macro>, String> {
+    handler::mutate_config(revision, "Duplicar macro", |cfg| {
+        let copy_id = cfg.duplicate_macro(id)?;
+        Ok(parser::stored_to_macro(cfg, &cfg.macros[&copy_id]))
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_step(
+    revision: u64,
+    macro_id: u64,
+    step: SequenceStep,
+    index: Option<usize>,
+) -> Result<Revisioned<SequenceStep>, String> {
+    handler::mutate_config(revision, "Añadir paso", |cfg| {
+        let id = cfg.create_step(macro_id, step, index)?;
+        cfg.resolve_step(id)
+            .context("Paso recién creado no encontrado")
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_step(revision: u64, step: SequenceStep) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Editar paso", |cfg| cfg.update_step(step))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_step(revision: u64, id: u64) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Eliminar paso", |cfg| cfg.delete_step(id))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn reorder_steps(
+    revision: u64,
+    macro_id: u64,
+    step_ids: Vec<u64>,
+) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Reordenar pasos", |cfg| {
+        cfg.reorder_steps(macro_id, step_ids)
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn undo() -> Result<Revisioned<Option<HistoryEntry>>, String> {
+    history::undo().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn redo() -> Result<Revisioned<Option<HistoryEntry>>, String> {
+    history::redo().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_history() -> HistoryLog {
+    history::log()
+}
+
+#[tauri::command]
+pub fn list_backups() -> Result<Vec<BackupInfo>, String> {
+    backup::list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn restore_backup(file_name: String) -> Result<Revisioned<()>, String> {
+    backup::restore(&file_name).map_err(|e| format!("{e:#}"))
+}
+
+#[tauri::command]
+pub fn export_profile(id: u64, path: String) -> Result<(), String> {
+    share::export_profile(id, Path::new(&path)).map_err(|e| format!("{e:#}"))
+}
+
+#[tauri::command]
+pub fn export_macro(id: u64, path: String) -> Result<(), String> {
+    share::export_macro(id, Path::new(&path)).map_err(|e| format!("{e:#}"))
+}
+
+#[tauri::command]
+pub fn import_shared(
+    revision: u64,
+    path: String,
+    target_profile_id: Option<u64>,
+) -> Result<Revisioned<ImportReport>, String> {
+    share::import(revision, Path::new(&path), target_profile_id).map_err(|e| format!("{e:#}"))
+}
+
+#[tauri::command]
+pub fn set_keyboard_layout(
+    revision: u64,
+    layout: KeyboardLayout,
+) -> Result<Revisioned<()>, String> {
+    handler::mutate_config(revision, "Cambiar distribución de teclado", |cfg| {
+        cfg.keyboard_layout = layout;
+        Ok(())
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn validate_config() -> Vec<ValidationIssue> {
+    validation::validate(&handler::get_config())
+}
@@ -0,0 +1,81 @@
+use std::hash::Hash;
+
+use ahash::AHashMap;
+
+use crate::{config::model::FlatStorage, domain::config::ConfigDiff};
+
+/// Compara dos configuraciones planas por ID.
+///
+/// Una macro cuenta como modificada si cambia su definición o cualquiera de sus pasos.
+pub fn diff(old: &FlatStorage, new: &FlatStorage) -> ConfigDiff {
+    let (added_profiles, removed_profiles, changed_profiles) =
+        diff_maps(&old.profiles, &new.profiles, |a, b| a == b);
+
+    let (added_macros, removed_macros, changed_macros) =
+        diff_maps(&old.macros, &new.macros, |a, b| {
+            a == b && old.macro_sequence(a.id) == new.macro_sequence(b.id)
+        });
+
+    let order = |storage: &FlatStorage| {
+        storage
+            .ordered_profiles()
+            .iter()
+            .map(|p| p.id)
+            .filter(|id| old.profiles.contains_key(id) && new.profiles.contains_key(id))
+            .collect::<Vec<_>>()
+    };
+
+    let common = |library: &[u64]| {
+        library
+            .iter()
+            .copied()
+            .filter(|id| old.library.contains(id) && new.library.contains(id))
+            .collect::<Vec<_>>()
+    };
+
+    ConfigDiff {
+        added_profiles,
+        removed_profiles,
+        changed_profiles,
+        profiles_reordered: order(old) != order(new),
+        added_macros,
+        removed_macros,
+        changed_macros,
+        library_reordered: common(&old.library) != common(&new.library),
+        keyboard_layout_changed: old.keyboard_layout != new.keyboard_layout,
+        selected_profile_changed: old.selected_profile_id != new.selected_profile_id,
+    }
+}
+
+fn diff_maps<K, V, F>(
+    old: &AHashMap<K, V>,
+    new: &AHashMap<K, V>,
+    same: F,
+) -> (Vec<K>, Vec<K>, Vec<K>)
+where
+    K: Copy + Eq + Hash + Ord,
+    F: Fn(&V, &V) -> bool,
+{
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+
+    for (id, value) in new {
+        match old.get(id) {
+            None => added.push(*id),
+            Some(prev) if !same(prev, value) => changed.push(*id),
+            Some(_) => {}
+        }
+    }
+
+    let mut removed = old
+        .keys()
+        .filter(|id| !new.contains_key(*id))
+        .copied()
+        .collect::<Vec<_>>();
+
+    added.sort_unstable();
+    removed.sort_unstable();
+    changed.sort_unstable();
+
+    (added, removed, changed)
+}
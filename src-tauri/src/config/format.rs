@@ -0,0 +1,164 @@
+use std::{fmt, path::Path};
+
+use anyhow::{Context, Result};
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::domain::config::Config;
+
+/// Formato del archivo de configuración, elegido por la extensión.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+/// Error de sintaxis con posición (1-based) para mostrar en la UI.
+#[derive(Debug)]
+pub struct ParseError {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "{} (línea {line}, columna {column})", self.message)
+            }
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ConfigFormat {
+    /// Extensiones desconocidas o ausentes se tratan como JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("toml") => Self::Toml,
+            Some("yaml" | "yml") => Self::Yaml,
+            _ => Self::Json,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Toml => "toml",
+            Self::Yaml => "yaml",
+        }
+    }
+
+    pub fn parse(self, bytes: &[u8]) -> Result<Config, ParseError> {
+        match self {
+            Self::Json => serde_json::from_slice(bytes).map_err(|e| ParseError {
+                message: e.to_string(),
+                line: Some(e.line()),
+                column: Some(e.column()),
+            }),
+            Self::Toml => {
+                let text = std::str::from_utf8(bytes).map_err(|e| ParseError {
+                    message: e.to_string(),
+                    line: None,
+                    column: None,
+                })?;
+                toml::from_str(text).map_err(|e| {
+                    let (line, column) = e.span().map(|span| line_column(text, span.start)).unzip();
+                    ParseError {
+                        message: e.message().to_string(),
+                        line,
+                        column,
+                    }
+                })
+            }
+            Self::Yaml => serde_yaml::from_slice(bytes).map_err(|e| {
+                let location = e.location();
+                ParseError {
+                    message: e.to_string(),
+                    line: location.as_ref().map(|l| l.line()),
+                    column: location.as_ref().map(|l| l.column()),
+                }
+            }),
+        }
+    }
+
+    /// Serializa la configuración. En TOML se conservan los comentarios de
+    /// `previous` (el contenido actual del archivo); JSON no los admite y YAML
+    /// se reescribe sin ellos.
+    pub fn serialize(self, config: &Config, previous: Option<&[u8]>) -> Result<Vec<u8>> {
+        match self {
+            Self::Json => {
+                serde_json::to_vec_pretty(config).context("No se pudo serializar la configuración")
+            }
+            Self::Toml => {
+                let text = toml::to_string_pretty(config)
+                    .context("No se pudo serializar la configuración")?;
+                let mut doc = text
+                    .parse::<DocumentMut>()
+                    .context("No se pudo generar el documento TOML")?;
+
+                let old = previous
+                    .and_then(|b| std::str::from_utf8(b).ok())
+                    .and_then(|t| t.parse::<DocumentMut>().ok());
+                if let Some(old) = old {
+                    keep_table_decor(old.as_table(), doc.as_table_mut());
+                    doc.set_trailing(old.trailing().clone());
+                }
+
+                Ok(doc.to_string().into_bytes())
+            }
+            Self::Yaml => serde_yaml::to_string(config)
+                .map(String::into_bytes)
+                .context("No se pudo serializar la configuración"),
+        }
+    }
+}
+
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+/// Copia comentarios y espaciado del documento anterior a las claves que siguen existiendo.
+fn keep_table_decor(old: &Table, new: &mut Table) {
+    *new.decor_mut() = old.decor().clone();
+
+    for (mut key, item) in new.iter_mut() {
+        if let Some((old_key, old_item)) = old.get_key_value(key.get()) {
+            *key.leaf_decor_mut() = old_key.leaf_decor().clone();
+            keep_item_decor(old_item, item);
+        }
+    }
+}
+
+fn keep_item_decor(old: &Item, new: &mut Item) {
+    match (old, new) {
+        (Item::Table(old), Item::Table(new)) => keep_table_decor(old, new),
+        // Perfiles, macros y pasos se emparejan por ID para sobrevivir a reordenamientos
+        (Item::ArrayOfTables(old), Item::ArrayOfTables(new)) => {
+            for (i, table) in new.iter_mut().enumerate() {
+                let matched = match table.get("id").and_then(|v| v.as_integer()) {
+                    Some(id) => old
+                        .iter()
+                        .find(|t| t.get("id").and_then(|v| v.as_integer()) == Some(id)),
+                    None => old.get(i),
+                };
+                if let Some(old_table) = matched {
+                    keep_table_decor(old_table, table);
+                }
+            }
+        }
+        (Item::Value(old), Item::Value(new)) => *new.decor_mut() = old.decor().clone(),
+        _ => {}
+    }
+}
@@ -1,45 +1,281 @@
+use crate::application::handler::try_get_app_handle;
+use crate::config::backup;
+use crate::config::diff::diff;
+use crate::config::format::{ConfigFormat, ParseError};
+use crate::config::history;
 use crate::config::model::FlatStorage;
 use crate::config::parser;
-use crate::domain::config::Config;
-use anyhow::{Context, Result};
+use crate::config::path::{config_path, sibling_path};
+use crate::domain::config::{ConfigDiff, ConfigRecovery, Revisioned, CONFIG_VERSION};
+use crate::domain::enums::ListenableChannel;
+use anyhow::{anyhow, bail, Context, Result};
 use parking_lot::RwLock;
 use std::fs;
 use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, LazyLock};
-
-pub(crate) const CONFIG_PATH: &str = "config.json";
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+use tracing::{error, info, warn};
 
 pub(crate) static CONFIG: LazyLock<Arc<RwLock<FlatStorage>>> =
-    LazyLock::new(|| Arc::new(RwLock::new(load_from_disk().unwrap_or_default())));
+    LazyLock::new(|| Arc::new(RwLock::new(load_or_recover())));
+
+/// `Some` mientras la configuración en disco no se pueda leer.
+static RECOVERY: LazyLock<RwLock<Option<ConfigRecovery>>> = LazyLock::new(|| RwLock::new(None));
+
+/// Sufijo de las copias de archivos dañados: `<nombre>.corrupt-<timestamp>.<ext>`.
+const CORRUPT_SUFFIX: &str = "corrupt-";
+
+/// Hash del último contenido escrito o leído, para ignorar nuestras propias escrituras.
+static KNOWN_CONTENT_HASH: AtomicU64 = AtomicU64::new(0);
 
 #[inline]
 pub(crate) fn load_from_disk() -> Result<FlatStorage> {
     // Cargar o crear la configuración
-    let cfg = match fs::read(CONFIG_PATH) {
-        Ok(bytes) if !bytes.is_empty() => serde_json::from_slice(&bytes)
-            .context("No se pudo deserializar el archivo de configuración"),
-        _ => Ok(Config::default()),
-    }?;
+    match fs::read(config_path()) {
+        Ok(bytes) => load_from_bytes(&bytes),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(FlatStorage::default()),
+        Err(e) => Err(e).context("No se pudo leer el archivo de configuración"),
+    }
+}
+
+#[inline]
+pub(crate) fn load_from_bytes(bytes: &[u8]) -> Result<FlatStorage> {
+    if bytes.is_empty() {
+        remember_content(bytes);
+        return Ok(FlatStorage::default());
+    }
+
+    let cfg = ConfigFormat::from_path(config_path())
+        .parse(bytes)
+        .context("No se pudo deserializar el archivo de configuración")?;
+    let flat = parser::config_to_flat(&cfg);
+
+    // Migrar formatos antiguos (IDs repetidos, sin contador) y reescribir
+    if cfg.version < CONFIG_VERSION {
+        info!(
+            "Migrando configuración de la versión {} a {}",
+            cfg.version, CONFIG_VERSION
+        );
+        if let Err(e) = save_to_disk(&flat) {
+            warn!("No se pudo guardar la configuración migrada: {e:?}");
+            remember_content(bytes);
+        }
+    } else {
+        remember_content(bytes);
+    }
 
     // Convertir y devolver
-    Ok(parser::config_to_flat(&cfg))
+    Ok(flat)
+}
+
+#[inline]
+fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[inline]
+fn remember_content(bytes: &[u8]) {
+    KNOWN_CONTENT_HASH.store(content_hash(bytes), Ordering::SeqCst);
+}
+
+/// `true` si el contenido coincide con lo último que escribimos o cargamos.
+#[inline]
+pub(crate) fn is_known_content(bytes: &[u8]) -> bool {
+    KNOWN_CONTENT_HASH.load(Ordering::SeqCst) == content_hash(bytes)
+}
+
+/// Carga inicial: si el archivo está dañado se aparta y se entra en modo
+/// recuperación en lugar de partir de una configuración vacía en silencio.
+/// Si solo no se pudo leer (permisos, archivo bloqueado) se deja donde está.
+fn load_or_recover() -> FlatStorage {
+    // Un arranque anterior apartó el archivo dañado y nadie lo ha devuelto
+    if !config_path().exists() {
+        if let Some(corrupt) = latest_corrupt_file() {
+            let e = anyhow!(
+                "No existe {}; la última configuración dañada está en {}",
+                config_path().display(),
+                corrupt.display()
+            );
+            warn!("{e}");
+            enter_recovery(&e, Some(corrupt));
+            return FlatStorage::default();
+        }
+    }
+
+    match load_from_disk() {
+        Ok(flat) => flat,
+        Err(e) => {
+            error!("No se pudo cargar {}: {:?}", config_path().display(), e);
+            let backup = if e.downcast_ref::<ParseError>().is_some() {
+                preserve_corrupt_file()
+            } else {
+                None
+            };
+            enter_recovery(&e, backup);
+            FlatStorage::default()
+        }
+    }
+}
+
+/// Renombra el archivo dañado a `<nombre>.corrupt-<timestamp>.<ext>` junto al
+/// original, para que ningún guardado posterior lo pise.
+fn preserve_corrupt_file() -> Option<PathBuf> {
+    let cfg_path = config_path();
+    let extension = ConfigFormat::from_path(cfg_path).extension();
+    let backup = sibling_path(&format!("{CORRUPT_SUFFIX}{}.{extension}", now_ms()));
+
+    match fs::rename(cfg_path, &backup) {
+        Ok(()) => {
+            warn!("Configuración dañada apartada a {}", backup.display());
+            Some(backup)
+        }
+        Err(e) => {
+            error!("No se pudo preservar la configuración dañada: {e}");
+            None
+        }
+    }
+}
+
+/// Copia dañada más reciente que apartó [`preserve_corrupt_file`], si queda alguna.
+pub(crate) fn latest_corrupt_file() -> Option<PathBuf> {
+    let prefix = sibling_path(CORRUPT_SUFFIX);
+    let dir = prefix.parent()?;
+    let prefix = prefix.file_name()?.to_string_lossy().into_owned();
+
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let timestamp = name
+                .strip_prefix(&prefix)?
+                .split('.')
+                .next()?
+                .parse::<u64>()
+                .ok()?;
+            Some((timestamp, entry.path()))
+        })
+        .max_by_key(|(timestamp, _)| *timestamp)
+        .map(|(_, path)| path)
+}
+
+pub(crate) fn enter_recovery(err: &anyhow::Error, backup_path: Option<PathBuf>) {
+    let parse_err = err.downcast_ref::<ParseError>();
+    let recovery = {
+        let mut current = RECOVERY.write();
+        // Conservar la copia de un fallo anterior si esta vez no se creó otra
+        let backup_path = backup_path
+            .map(|p| p.display().to_string())
+            .or_else(|| current.as_ref().and_then(|r| r.backup_path.clone()));
+
+        let recovery = ConfigRecovery {
+            message: format!("{err:#}"),
+            line: parse_err.and_then(|e| e.line),
+            column: parse_err.and_then(|e| e.column),
+            backup_path,
+        };
+        *current = Some(recovery.clone());
+        recovery
+    };
+
+    if let Some(handle) = try_get_app_handle() {
+        let channel = &ListenableChannel::ConfigRecovery.to_string();
+        if let Err(e) = handle.emit(channel, Some(recovery)) {
+            error!("Error al emitir evento: {e}");
+        }
+    }
+}
+
+pub(crate) fn leave_recovery() {
+    if RECOVERY.write().take().is_none() {
+        return;
+    }
+
+    info!("Modo recuperación de configuración finalizado");
+    if let Some(handle) = try_get_app_handle() {
+        let channel = &ListenableChannel::ConfigRecovery.to_string();
+        if let Err(e) = handle.emit(channel, None::<ConfigRecovery>) {
+            error!("Error al emitir evento: {e}");
+        }
+    }
+}
+
+#[inline]
+pub fn get_recovery() -> Option<ConfigRecovery> {
+    LazyLock::force(&CONFIG);
+    RECOVERY.read().clone()
+}
+
+/// Vuelve a leer el archivo de configuración; si es válido sale del modo recuperación.
+pub fn reload() -> Result<()> {
+    // Con el archivo dañado apartado, su ausencia no es una configuración vacía válida
+    if RECOVERY.read().is_some() && !config_path().exists() {
+        bail!(
+            "No existe {}; corrija la copia apartada y devuélvala a su sitio",
+            config_path().display()
+        );
+    }
+    match load_from_disk() {
+        Ok(flat) => {
+            replace_config(flat);
+            leave_recovery();
+            Ok(())
+        }
+        Err(e) => {
+            enter_recovery(&e, None);
+            Err(e)
+        }
+    }
+}
+
+/// Acepta la configuración actual en memoria y desbloquea el guardado.
+/// La copia del archivo dañado, si existe, se mantiene en disco.
+pub fn discard_corrupt() {
+    LazyLock::force(&CONFIG);
+    leave_recovery();
 }
 
 #[inline]
 pub(crate) fn save_to_disk(store: &FlatStorage) -> Result<()> {
-    // Serializar la configuración
-    let json = serde_json::to_vec_pretty(&parser::flat_to_config(store))
-        .context("No se pudo serializar la configuración")?;
+    let path = config_path();
+
+    // Serializar la configuración conservando lo que el formato permita del archivo actual
+    let previous = fs::read(path).ok();
+    let bytes = ConfigFormat::from_path(path)
+        .serialize(&parser::flat_to_config(store), previous.as_deref())?;
+
+    // Copia del archivo actual antes de reemplazarlo
+    backup::maybe_backup();
 
-    let cfg_path = Path::new(CONFIG_PATH);
-    let tmp_path = cfg_path.with_extension("tmp");
+    // Antes del rename, para que el watchdog reconozca el evento como propio
+    remember_content(&bytes);
+
+    write_atomic(path, &bytes)
+}
+
+#[inline]
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Escribe en un archivo temporal y lo renombra sobre `path`.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
 
     {
         let mut file =
             BufWriter::new(File::create(&tmp_path).context("No se pudo crear archivo temporal")?);
-        file.write_all(&json)?;
+        file.write_all(bytes)?;
         file.flush()?;
         file.get_mut()
             .sync_all()
@@ -47,35 +283,95 @@ pub(crate) fn save_to_disk(store: &FlatStorage) -> Result<()> {
     }
 
     // Renombrar atómicamente
-    fs::rename(&tmp_path, cfg_path).with_context(|| {
+    fs::rename(&tmp_path, path).with_context(|| {
         format!(
             "No se pudo reemplazar {} por {}",
             tmp_path.display(),
-            cfg_path.display()
+            path.display()
         )
     })?;
 
     Ok(())
 }
 
-// #[inline]
-// pub fn get_config_path() -> PathBuf {
-//     PathBuf::from(CONFIG_PATH)
-// }
+/// Sustituye la configuración en memoria por una leída de disco y devuelve los cambios.
+pub(crate) fn replace_config(mut new: FlatStorage) -> ConfigDiff {
+    let mut cfg = CONFIG.write();
+    let changes = diff(&cfg, &new);
+    // La revisión nunca retrocede aunque el archivo venga de fuera
+    new.revision = cfg.revision + 1;
+    *cfg = new;
+    changes
+}
+
+#[inline]
+pub(crate) fn ensure_writable() -> Result<()> {
+    if let Some(recovery) = RECOVERY.read().as_ref() {
+        bail!(
+            "Guardado bloqueado: la configuración está en modo recuperación ({})",
+            recovery.message
+        );
+    }
+    Ok(())
+}
 
 #[inline]
 pub fn get_config() -> FlatStorage {
     CONFIG.read().clone()
 }
 
+/// Aplica un cambio y lo registra en el historial con `description`.
 #[inline]
-pub fn save_config<F, R>(f: F) -> Result<R>
+pub fn save_config<F, R>(description: &str, f: F) -> Result<R>
 where
     F: FnOnce(&mut FlatStorage) -> R,
 {
     let mut flat = CONFIG.write();
+    ensure_writable()?;
+
+    let before = flat.clone();
+    let revision = flat.revision;
     let result = f(&mut flat);
+    flat.revision = revision + 1;
     save_to_disk(&flat)?;
+    let recorded = history::record(description, &before, &flat);
+    drop(flat);
+    if recorded {
+        history::persist();
+    }
 
     Ok(result)
 }
+
+/// Aplica un cambio granular si la revisión del cliente sigue vigente.
+///
+/// El cambio se hace sobre una copia, así un error a mitad de camino no deja
+/// la configuración a medias.
+pub fn mutate_config<F, R>(expected_revision: u64, description: &str, f: F) -> Result<Revisioned<R>>
+where
+    F: FnOnce(&mut FlatStorage) -> Result<R>,
+{
+    let mut flat = CONFIG.write();
+    ensure_writable()?;
+
+    if flat.revision != expected_revision {
+        bail!(
+            "Conflicto de revisión: se esperaba {expected_revision} pero la actual es {}",
+            flat.revision
+        );
+    }
+
+    let mut draft = flat.clone();
+    let data = f(&mut draft)?;
+    draft.revision += 1;
+    save_to_disk(&draft)?;
+    let recorded = history::record(description, &flat, &draft);
+    *flat = draft;
+    let revision = flat.revision;
+    drop(flat);
+    if recorded {
+        history::persist();
+    }
+
+    Ok(Revisioned { revision, data })
+}
@@ -0,0 +1,255 @@
+use std::{fs, sync::LazyLock};
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::{
+    config::{
+        diff::diff,
+        handler::{ensure_writable, now_ms, save_to_disk, write_atomic, CONFIG},
+        model::FlatStorage,
+        parser,
+        path::sibling_path,
+    },
+    domain::{
+        config::{Config, Revisioned},
+        history::{HistoryEntry, HistoryLog},
+    },
+};
+
+/// Máximo de cambios que se pueden deshacer.
+const MAX_ENTRIES: usize = 50;
+const HISTORY_SUFFIX: &str = "history.json";
+
+/// Estado completo de la configuración junto a la edición que lo generó.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Snapshot {
+    entry: HistoryEntry,
+    config: Config,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl History {
+    /// Añade el estado previo a una edición; lo que se podía rehacer se pierde.
+    fn push(&mut self, snapshot: Snapshot) {
+        self.undo.push(snapshot);
+        if self.undo.len() > MAX_ENTRIES {
+            let excess = self.undo.len() - MAX_ENTRIES;
+            self.undo.drain(..excess);
+        }
+        self.redo.clear();
+    }
+
+    /// Saca la última entrada de deshacer (o de rehacer) y deja `current` en la
+    /// pila contraria con la misma descripción. Repetirlo en sentido contrario
+    /// con el estado devuelto lo revierte.
+    fn travel(&mut self, undo: bool, current: Config) -> Option<Snapshot> {
+        let (from, to) = if undo {
+            (&mut self.undo, &mut self.redo)
+        } else {
+            (&mut self.redo, &mut self.undo)
+        };
+
+        let snapshot = from.pop()?;
+        to.push(Snapshot {
+            entry: snapshot.entry.clone(),
+            config: current,
+        });
+        Some(snapshot)
+    }
+}
+
+static HISTORY: LazyLock<Mutex<History>> = LazyLock::new(|| Mutex::new(load()));
+
+/// Ordena las escrituras del archivo para que la última refleje el último estado.
+static PERSIST_LOCK: Mutex<()> = Mutex::new(());
+
+fn load() -> History {
+    let path = sibling_path(HISTORY_SUFFIX);
+    match fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            warn!(
+                "Historial ilegible en {}, se empieza vacío: {e}",
+                path.display()
+            );
+            History::default()
+        }),
+        Err(_) => History::default(),
+    }
+}
+
+/// Escribe el historial en disco. Se llama con `CONFIG` ya liberado: son
+/// hasta [`MAX_ENTRIES`] configuraciones completas y el disco no debe frenar
+/// a quien lee la configuración.
+pub(crate) fn persist() {
+    let _guard = PERSIST_LOCK.lock();
+    // La copia se serializa sin el cerrojo para no frenar a `record`
+    let history = HISTORY.lock().clone();
+    let result = serde_json::to_vec(&history)
+        .context("No se pudo serializar el historial")
+        .and_then(|json| write_atomic(&sibling_path(HISTORY_SUFFIX), &json));
+
+    if let Err(e) = result {
+        error!("No se pudo guardar el historial: {e:?}");
+    }
+}
+
+/// Guarda en memoria el estado previo a un cambio; `true` si hay que llamar a
+/// [`persist`] tras soltar `CONFIG`. Cambiar solo el perfil activo no cuenta
+/// como edición.
+#[must_use]
+pub(crate) fn record(description: &str, before: &FlatStorage, after: &FlatStorage) -> bool {
+    if diff(before, after).is_selection_only() {
+        return false;
+    }
+
+    HISTORY.lock().push(Snapshot {
+        entry: HistoryEntry {
+            description: description.to_string(),
+            timestamp: now_ms(),
+        },
+        config: parser::flat_to_config(before),
+    });
+    true
+}
+
+pub fn undo() -> Result<Revisioned<Option<HistoryEntry>>> {
+    step(true)
+}
+
+pub fn redo() -> Result<Revisioned<Option<HistoryEntry>>> {
+    step(false)
+}
+
+pub fn log() -> HistoryLog {
+    let history = HISTORY.lock();
+    HistoryLog {
+        undo: history.undo.iter().map(|s| s.entry.clone()).collect(),
+        redo: history.redo.iter().map(|s| s.entry.clone()).collect(),
+    }
+}
+
+fn step(undo: bool) -> Result<Revisioned<Option<HistoryEntry>>> {
+    let mut flat = CONFIG.write();
+    ensure_writable()?;
+
+    let mut history = HISTORY.lock();
+    let Some(snapshot) = history.travel(undo, parser::flat_to_config(&flat)) else {
+        return Ok(Revisioned {
+            revision: flat.revision,
+            data: None,
+        });
+    };
+
+    let mut restored = parser::config_to_flat(&snapshot.config);
+    restored.revision = flat.revision + 1;
+    restored.next_id = restored.next_id.max(flat.next_id);
+    // El perfil activo no forma parte del historial
+    restored.selected_profile_id = flat
+        .selected_profile_id
+        .filter(|id| restored.profiles.contains_key(id));
+
+    if let Err(e) = save_to_disk(&restored) {
+        history.travel(!undo, snapshot.config);
+        return Err(e);
+    }
+
+    *flat = restored;
+    let revision = flat.revision;
+    drop(history);
+    drop(flat);
+    persist();
+
+    Ok(Revisioned {
+        revision,
+        data: Some(snapshot.entry),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Estado distinguible por `next_id`.
+    fn state(n: u64) -> Config {
+        Config {
+            next_id: n,
+            ..Default::default()
+        }
+    }
+
+    fn edit(history: &mut History, before: u64) {
+        history.push(Snapshot {
+            entry: HistoryEntry {
+                description: format!("edición {before}"),
+                timestamp: before,
+            },
+            config: state(before),
+        });
+    }
+
+    #[test]
+    fn undo_then_redo_round_trips() {
+        let mut history = History::default();
+        edit(&mut history, 1);
+
+        let undone = history.travel(true, state(2)).unwrap();
+        assert_eq!(undone.config, state(1));
+        assert_eq!(history.undo.len(), 0);
+
+        let redone = history.travel(false, state(1)).unwrap();
+        assert_eq!(redone.config, state(2));
+        assert_eq!(redone.entry.description, "edición 1");
+        assert_eq!(history.undo.len(), 1);
+        assert!(history.redo.is_empty());
+        assert!(history.travel(false, state(2)).is_none());
+    }
+
+    #[test]
+    fn new_edit_discards_redo() {
+        let mut history = History::default();
+        edit(&mut history, 1);
+        edit(&mut history, 2);
+        history.travel(true, state(3)).unwrap();
+        assert_eq!(history.redo.len(), 1);
+
+        edit(&mut history, 2);
+        assert!(history.redo.is_empty());
+        assert!(history.travel(false, state(4)).is_none());
+    }
+
+    #[test]
+    fn failed_step_is_reverted() {
+        let mut history = History::default();
+        edit(&mut history, 1);
+
+        let undone = history.travel(true, state(2)).unwrap();
+        history.travel(false, undone.config);
+
+        assert_eq!(history.undo.len(), 1);
+        assert_eq!(history.undo[0].config, state(1));
+        assert!(history.redo.is_empty());
+    }
+
+    #[test]
+    fn keeps_only_the_latest_entries() {
+        let mut history = History::default();
+        for n in 0..MAX_ENTRIES as u64 + 10 {
+            edit(&mut history, n);
+        }
+
+        assert_eq!(history.undo.len(), MAX_ENTRIES);
+        assert_eq!(history.undo[0].config, state(10));
+        assert_eq!(
+            history.undo.last().unwrap().config,
+            state(MAX_ENTRIES as u64 + 9)
+        );
+    }
+}
@@ -1,5 +1,13 @@
+pub mod backup;
 pub mod commands;
+pub mod diff;
+pub mod format;
 pub mod handler;
+pub mod history;
 pub mod model;
+pub mod mutations;
 pub mod parser;
+pub mod path;
+pub mod share;
+pub mod validation;
 pub mod watchdog;
@@ -1,30 +1,44 @@
 use ahash::AHashMap;
 use serde::{Deserialize, Serialize};
 
-use crate::domain::{macros::KeyCombination, sequence_step::SequenceStep};
+use crate::domain::{enums::KeyboardLayout, macros::KeyCombination, sequence_step::SequenceStep};
 
 #[derive(Debug, Default, Clone)]
 pub struct FlatStorage {
     pub profiles: AHashMap<u64, StoredProfile>,
+    /// Orden definido por el usuario; el mapa de perfiles no conserva ninguno.
+    pub profile_order: Vec<u64>,
     pub macros: AHashMap<u64, StoredMacro>,
     pub steps: AHashMap<u64, SequenceStep>,
+    /// Los pasos compuestos se guardan con los cuerpos vacíos; sus hijos van
+    /// aquí, una lista ordenada por cuerpo.
+    pub step_bodies: AHashMap<u64, Vec<Vec<u64>>>,
+    /// Macros de la biblioteca, en orden. Sus entradas viven en `macros`.
+    pub library: Vec<u64>,
+    pub keyboard_layout: KeyboardLayout,
     pub selected_profile_id: Option<u64>,
+    /// Se incrementa en cada cambio; permite detectar ediciones concurrentes.
+    pub revision: u64,
+    /// Contador monotónico de IDs, compartido por perfiles, macros y pasos.
+    pub next_id: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct StoredProfile {
     pub id: u64,
     pub name: String,
     pub function_key: Option<String>,
     pub macro_ids: Vec<u64>,
+    pub persist_variables: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct StoredMacro {
     pub id: u64,
     pub name: String,
     pub trigger: KeyCombination,
     pub sequence_step_ids: Vec<u64>,
+    pub script: Option<String>,
 }
 
 impl FlatStorage {
@@ -43,4 +57,69 @@ impl FlatStorage {
     pub fn find_profile(&self, id: &u64) -> Option<&StoredProfile> {
         self.profiles.get(id)
     }
+
+    /// Pasos de una macro, en orden y con los cuerpos anidados ya reconstruidos.
+    pub fn macro_sequence(&self, id: u64) -> Option<Vec<SequenceStep>> {
+        self.macros
+            .get(&id)
+            .map(|mac| self.resolve_steps(&mac.sequence_step_ids))
+    }
+
+    /// Pasos que se ejecutan al lanzar la macro: su script si lo tiene o, si no,
+    /// su secuencia.
+    pub fn macro_program(&self, id: u64) -> Option<Vec<SequenceStep>> {
+        let mac = self.macros.get(&id)?;
+        Some(self.program_of(mac))
+    }
+
+    pub fn program_of(&self, mac: &StoredMacro) -> Vec<SequenceStep> {
+        match &mac.script {
+            Some(source) => vec![SequenceStep::Script {
+                id: 0,
+                source: source.clone(),
+            }],
+            None => self.resolve_steps(&mac.sequence_step_ids),
+        }
+    }
+
+    /// Reconstruye un paso con sus hijos desde `step_bodies`.
+    pub fn resolve_step(&self, id: u64) -> Option<SequenceStep> {
+        let mut step = self.steps.get(&id)?.clone();
+        if let Some(bodies) = self.step_bodies.get(&id) {
+            for (body, children) in step.bodies_mut().into_iter().zip(bodies) {
+                *body = self.resolve_steps(children);
+            }
+        }
+        Some(step)
+    }
+
+    pub fn resolve_steps(&self, ids: &[u64]) -> Vec<SequenceStep> {
+        ids.iter()
+            .filter_map(|sid| self.resolve_step(*sid))
+            .collect()
+    }
+
+    /// Perfiles en el orden del usuario. Los que falten en `profile_order`
+    /// van al final ordenados por ID para que el resultado sea estable.
+    pub fn ordered_profiles(&self) -> Vec<&StoredProfile> {
+        let mut missing = self
+            .profiles
+            .values()
+            .filter(|p| !self.profile_order.contains(&p.id))
+            .collect::<Vec<_>>();
+        missing.sort_unstable_by_key(|p| p.id);
+
+        self.profile_order
+            .iter()
+            .filter_map(|id| self.profiles.get(id))
+            .chain(missing)
+            .collect()
+    }
+
+    /// Reserva un ID nuevo. Nunca se reutilizan IDs de elementos borrados.
+    pub fn allocate_id(&mut self) -> u64 {
+        let id = self.next_id.max(1);
+        self.next_id = id + 1;
+        id
+    }
 }
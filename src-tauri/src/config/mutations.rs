@@ -0,0 +1,344 @@
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    config::model::{FlatStorage, StoredMacro, StoredProfile},
+    domain::{macros::KeyCombination, sequence_step::SequenceStep},
+};
+
+/// Operaciones granulares sobre la configuración plana.
+///
+/// Los IDs siempre los asigna el backend; los que envíe el cliente se ignoran.
+impl FlatStorage {
+    pub fn create_profile(&mut self, name: String, function_key: Option<String>) -> u64 {
+        let id = self.allocate_id();
+        self.profiles.insert(
+            id,
+            StoredProfile {
+                id,
+                name,
+                function_key,
+                macro_ids: Vec::new(),
+                persist_variables: false,
+            },
+        );
+        self.profile_order.push(id);
+        id
+    }
+
+    pub fn update_profile(
+        &mut self,
+        id: u64,
+        name: String,
+        function_key: Option<String>,
+    ) -> Result<()> {
+        let profile = self.profile_mut(id)?;
+        profile.name = name;
+        profile.function_key = function_key;
+        Ok(())
+    }
+
+    pub fn set_persist_variables(&mut self, id: u64, persist: bool) -> Result<()> {
+        self.profile_mut(id)?.persist_variables = persist;
+        Ok(())
+    }
+
+    pub fn delete_profile(&mut self, id: u64) -> Result<()> {
+        let profile = self
+            .profiles
+            .remove(&id)
+            .with_context(|| format!("Perfil {id} no encontrado"))?;
+        self.profile_order.retain(|p| *p != id);
+
+        for macro_id in profile.macro_ids {
+            self.remove_macro_entry(macro_id);
+        }
+
+        if self.selected_profile_id == Some(id) {
+            self.selected_profile_id = None;
+        }
+        Ok(())
+    }
+
+    pub fn reorder_profiles(&mut self, order: Vec<u64>) -> Result<()> {
+        let current = self
+            .ordered_profiles()
+            .iter()
+            .map(|p| p.id)
+            .collect::<Vec<_>>();
+        ensure_permutation(&current, &order)?;
+        self.profile_order = order;
+        Ok(())
+    }
+
+    pub fn create_macro(
+        &mut self,
+        profile_id: u64,
+        name: String,
+        trigger: KeyCombination,
+        sequence: Vec<SequenceStep>,
+    ) -> Result<u64> {
+        self.profile_mut(profile_id)?;
+
+        let id = self.insert_macro(name, trigger, sequence)?;
+        self.profile_mut(profile_id)?.macro_ids.push(id);
+        Ok(id)
+    }
+
+    /// Crea una macro en la biblioteca compartida. No tiene disparador propio.
+    pub fn create_library_macro(
+        &mut self,
+        name: String,
+        sequence: Vec<SequenceStep>,
+    ) -> Result<u64> {
+        let id = self.insert_macro(name, KeyCombination::default(), sequence)?;
+        self.library.push(id);
+        Ok(id)
+    }
+
+    pub fn update_macro(&mut self, id: u64, name: String, trigger: KeyCombination) -> Result<()> {
+        let mac = self.macro_mut(id)?;
+        mac.name = name;
+        mac.trigger = trigger;
+        Ok(())
+    }
+
+    /// Con script, la macro ejecuta el script en lugar de sus pasos, que se conservan.
+    pub fn set_macro_script(&mut self, id: u64, script: Option<String>) -> Result<()> {
+        self.macro_mut(id)?.script = script;
+        Ok(())
+    }
+
+    pub fn delete_macro(&mut self, id: u64) -> Result<()> {
+        self.macro_mut(id)?;
+
+        for profile in self.profiles.values_mut() {
+            profile.macro_ids.retain(|m| *m != id);
+        }
+        self.library.retain(|m| *m != id);
+        self.remove_macro_entry(id);
+        Ok(())
+    }
+
+    pub fn reorder_macros(&mut self, profile_id: u64, order: Vec<u64>) -> Result<()> {
+        let profile = self.profile_mut(profile_id)?;
+        ensure_permutation(&profile.macro_ids, &order)?;
+        profile.macro_ids = order;
+        Ok(())
+    }
+
+    pub fn reorder_library(&mut self, order: Vec<u64>) -> Result<()> {
+        ensure_permutation(&self.library, &order)?;
+        self.library = order;
+        Ok(())
+    }
+
+    /// Mueve una macro a otro perfil, al final o en la posición indicada.
+    /// También sirve para sacar una macro de la biblioteca.
+    pub fn move_macro(
+        &mut self,
+        id: u64,
+        target_profile_id: u64,
+        index: Option<usize>,
+    ) -> Result<()> {
+        self.macro_mut(id)?;
+        self.profile_mut(target_profile_id)?;
+
+        for profile in self.profiles.values_mut() {
+            profile.macro_ids.retain(|m| *m != id);
+        }
+        self.library.retain(|m| *m != id);
+
+        let target = self.profile_mut(target_profile_id)?;
+        let index = index.unwrap_or(target.macro_ids.len());
+        target
+            .macro_ids
+            .insert(index.min(target.macro_ids.len()), id);
+        Ok(())
+    }
+
+    /// Copia una macro con pasos nuevos y la coloca justo después de la original,
+    /// en su perfil o en la biblioteca.
+    pub fn duplicate_macro(&mut self, id: u64) -> Result<u64> {
+        let original = self.macro_mut(id)?.clone();
+        let sequence = self.macro_sequence(id).unwrap_or_default();
+        let profile_id = self.profile_of_macro(id);
+        if profile_id.is_none() && !self.library.contains(&id) {
+            bail!("La macro {id} no pertenece a ningún perfil ni a la biblioteca");
+        }
+
+        let copy_id = self.insert_macro(
+            format!("{} (copia)", original.name),
+            original.trigger,
+            sequence,
+        )?;
+        self.set_macro_script(copy_id, original.script)?;
+
+        let macro_ids = match profile_id {
+            Some(profile_id) => &mut self.profile_mut(profile_id)?.macro_ids,
+            None => &mut self.library,
+        };
+        let position = macro_ids
+            .iter()
+            .position(|m| *m == id)
+            .map_or(macro_ids.len(), |p| p + 1);
+        macro_ids.insert(position, copy_id);
+
+        Ok(copy_id)
+    }
+
+    pub fn create_step(
+        &mut self,
+        macro_id: u64,
+        step: SequenceStep,
+        index: Option<usize>,
+    ) -> Result<u64> {
+        self.macro_mut(macro_id)?;
+
+        let id = self.insert_step(step);
+
+        let mac = self.macro_mut(macro_id)?;
+        let index = index.unwrap_or(mac.sequence_step_ids.len());
+        mac.sequence_step_ids
+            .insert(index.min(mac.sequence_step_ids.len()), id);
+        Ok(id)
+    }
+
+    /// Sustituye el paso. En los compuestos los cuerpos enviados reemplazan a los anteriores.
+    pub fn update_step(&mut self, mut step: SequenceStep) -> Result<()> {
+        let id = step.id();
+        if !self.steps.contains_key(&id) {
+            bail!("Paso {id} no encontrado");
+        }
+
+        for child in self.step_bodies.remove(&id).into_iter().flatten().flatten() {
+            self.remove_step_tree(child);
+        }
+        self.insert_bodies(id, &mut step);
+
+        self.steps.insert(id, step);
+        Ok(())
+    }
+
+    pub fn delete_step(&mut self, id: u64) -> Result<()> {
+        if !self.steps.contains_key(&id) {
+            bail!("Paso {id} no encontrado");
+        }
+        self.remove_step_tree(id);
+
+        for mac in self.macros.values_mut() {
+            mac.sequence_step_ids.retain(|s| *s != id);
+        }
+        for children in self.step_bodies.values_mut().flatten() {
+            children.retain(|s| *s != id);
+        }
+        Ok(())
+    }
+
+    pub fn reorder_steps(&mut self, macro_id: u64, order: Vec<u64>) -> Result<()> {
+        let mac = self.macro_mut(macro_id)?;
+        ensure_permutation(&mac.sequence_step_ids, &order)?;
+        mac.sequence_step_ids = order;
+        Ok(())
+    }
+
+    pub fn profile_of_macro(&self, macro_id: u64) -> Option<u64> {
+        self.profiles
+            .values()
+            .find(|p| p.macro_ids.contains(&macro_id))
+            .map(|p| p.id)
+    }
+
+    /// Crea la entrada de la macro y sus pasos sin añadirla a ninguna lista.
+    fn insert_macro(
+        &mut self,
+        name: String,
+        trigger: KeyCombination,
+        sequence: Vec<SequenceStep>,
+    ) -> Result<u64> {
+        let id = self.allocate_id();
+        self.macros.insert(
+            id,
+            StoredMacro {
+                id,
+                name,
+                trigger,
+                sequence_step_ids: Vec::new(),
+                script: None,
+            },
+        );
+
+        for step in sequence {
+            self.create_step(id, step, None)?;
+        }
+        Ok(id)
+    }
+
+    fn profile_mut(&mut self, id: u64) -> Result<&mut StoredProfile> {
+        self.profiles
+            .get_mut(&id)
+            .with_context(|| format!("Perfil {id} no encontrado"))
+    }
+
+    fn macro_mut(&mut self, id: u64) -> Result<&mut StoredMacro> {
+        self.macros
+            .get_mut(&id)
+            .with_context(|| format!("Macro {id} no encontrada"))
+    }
+
+    /// Elimina la macro y sus pasos sin tocar las listas de los perfiles.
+    fn remove_macro_entry(&mut self, id: u64) {
+        if let Some(mac) = self.macros.remove(&id) {
+            for step_id in mac.sequence_step_ids {
+                self.remove_step_tree(step_id);
+            }
+        }
+    }
+
+    /// Guarda el paso con un ID nuevo; los cuerpos de los compuestos se guardan aparte.
+    fn insert_step(&mut self, mut step: SequenceStep) -> u64 {
+        let id = self.allocate_id();
+        step.set_id(id);
+
+        self.insert_bodies(id, &mut step);
+        self.steps.insert(id, step);
+        id
+    }
+
+    /// Vacía los cuerpos del paso y guarda sus hijos con IDs nuevos en `step_bodies`.
+    fn insert_bodies(&mut self, id: u64, step: &mut SequenceStep) {
+        let bodies = step
+            .bodies_mut()
+            .into_iter()
+            .map(std::mem::take)
+            .collect::<Vec<_>>();
+        if bodies.is_empty() {
+            return;
+        }
+
+        let children = bodies
+            .into_iter()
+            .map(|body| body.into_iter().map(|c| self.insert_step(c)).collect())
+            .collect();
+        self.step_bodies.insert(id, children);
+    }
+
+    /// Elimina el paso y sus hijos sin tocar las listas que lo referencian.
+    fn remove_step_tree(&mut self, id: u64) {
+        self.steps.remove(&id);
+        for child in self.step_bodies.remove(&id).into_iter().flatten().flatten() {
+            self.remove_step_tree(child);
+        }
+    }
+}
+
+fn ensure_permutation(current: &[u64], order: &[u64]) -> Result<()> {
+    let mut a = current.to_vec();
+    let mut b = order.to_vec();
+    a.sort_unstable();
+    b.sort_unstable();
+
+    if a != b {
+        bail!("El nuevo orden debe contener exactamente los mismos elementos");
+    }
+    Ok(())
+}
@@ -1,93 +1,558 @@
 use ahash::AHashMap;
+use tracing::warn;
 
 use crate::{
     config::model::{FlatStorage, StoredMacro, StoredProfile},
-    domain::{config::Config, macros::Macro, profiles::Profile, sequence_step::SequenceStep},
+    domain::{
+        config::{Config, CONFIG_VERSION},
+        macros::Macro,
+        profiles::Profile,
+        sequence_step::SequenceStep,
+    },
 };
 
+/// Convierte la configuración anidada en tablas planas.
+///
+/// Los IDs repetidos dentro de una misma tabla (datos heredados de cuando el
+/// cliente generaba los IDs) se renumeran en lugar de sobrescribirse. Un
+/// `CallMacro` a un ID repetido va a la macro de su mismo perfil, luego a la
+/// de la biblioteca y, si no, a la primera que tenía ese ID. El perfil activo
+/// sigue siendo el primero con su ID.
 #[inline]
 pub fn config_to_flat(config: &Config) -> FlatStorage {
     let mut profiles = AHashMap::new();
+    let mut profile_order = Vec::with_capacity(config.profiles.len());
     let mut macros = AHashMap::new();
     let mut steps = AHashMap::new();
+    let mut step_bodies = AHashMap::new();
+    let mut next_id = config.next_id.max(max_id(config) + 1);
+    let mut macro_ids = MacroIds::default();
+    let mut scopes = Vec::new();
 
-    for profile in &config.profiles {
-        // 1.1  Pasos anidados → tabla plana
-        for mac in &profile.macros {
-            for step in &mac.sequence {
-                steps.insert(step_id(step), step.clone());
-            }
-            macros.insert(
-                mac.id,
-                StoredMacro {
-                    id: mac.id,
-                    name: mac.name.clone(),
-                    trigger: mac.trigger.clone(),
-                    sequence_step_ids: mac.sequence.iter().map(step_id).collect(),
-                },
-            );
-        }
+    for (index, profile) in config.profiles.iter().enumerate() {
+        // 1.1  Macros anidadas → tablas planas
+        let profile_macro_ids = profile
+            .macros
+            .iter()
+            .map(|mac| {
+                let mid =
+                    flatten_macro(mac, &mut macros, &mut steps, &mut step_bodies, &mut next_id);
+                macro_ids.insert(Some(index), mac.id, mid);
+                scopes.push((mid, Some(index)));
+                mid
+            })
+            .collect();
 
         // 1.2  Perfil sin macros reales
+        let pid = unique_id(&profiles, profile.id, &mut next_id, "perfil");
+        if pid != profile.id && config.selected_profile_id == Some(profile.id) {
+            warn!(
+                "El perfil activo {} estaba repetido; sigue activo el primero",
+                profile.id
+            );
+        }
         profiles.insert(
-            profile.id,
+            pid,
             StoredProfile {
-                id: profile.id,
+                id: pid,
                 name: profile.name.clone(),
                 function_key: profile.function_key.clone(),
-                macro_ids: profile.macros.iter().map(|m| m.id).collect(),
+                macro_ids: profile_macro_ids,
+                persist_variables: profile.persist_variables,
             },
         );
+        profile_order.push(pid);
+    }
+
+    // 2.  Biblioteca compartida, mismas tablas que las macros de los perfiles
+    let library = config
+        .library
+        .iter()
+        .map(|mac| {
+            let mid = flatten_macro(mac, &mut macros, &mut steps, &mut step_bodies, &mut next_id);
+            macro_ids.insert(None, mac.id, mid);
+            scopes.push((mid, None));
+            mid
+        })
+        .collect();
+
+    // 3.  Con todos los IDs definitivos, las llamadas apuntan a ellos
+    for (mid, scope) in scopes {
+        remap_calls(
+            &macros[&mid].sequence_step_ids,
+            scope,
+            &mut steps,
+            &step_bodies,
+            &macro_ids,
+        );
     }
 
     FlatStorage {
         profiles,
+        profile_order,
         macros,
         steps,
+        step_bodies,
+        library,
+        keyboard_layout: config.keyboard_layout,
         selected_profile_id: config.selected_profile_id,
+        revision: 0,
+        next_id,
     }
 }
 
-#[inline]
-pub fn flat_to_config(storage: &FlatStorage) -> Config {
-    let mut profiles = Vec::new();
+/// ID definitivo de cada macro según su ID original y desde dónde se la llama.
+#[derive(Debug, Default)]
+struct MacroIds {
+    /// (índice del perfil o `None` para la biblioteca, ID original) → ID definitivo.
+    scoped: AHashMap<(Option<usize>, u64), u64>,
+    /// Primera macro con cada ID original y cuántas lo usaban.
+    first: AHashMap<u64, (u64, usize)>,
+}
+
+impl MacroIds {
+    fn insert(&mut self, scope: Option<usize>, original: u64, id: u64) {
+        if self.scoped.contains_key(&(scope, original)) {
+            warn!(
+                "ID de macro {original} repetido en el mismo perfil; sus llamadas van a la primera"
+            );
+        } else {
+            self.scoped.insert((scope, original), id);
+        }
+        self.first.entry(original).or_insert((id, 0)).1 += 1;
+    }
+
+    fn resolve(&self, scope: Option<usize>, original: u64) -> Option<u64> {
+        if let Some(id) = self
+            .scoped
+            .get(&(scope, original))
+            .or_else(|| self.scoped.get(&(None, original)))
+        {
+            return Some(*id);
+        }
+
+        let (id, owners) = *self.first.get(&original)?;
+        if owners > 1 {
+            warn!(
+                "La llamada a la macro {original} es ambigua; se usa la primera con ese ID ({id})"
+            );
+        }
+        Some(id)
+    }
+}
+
+/// Apunta los `CallMacro` de los pasos (y sus cuerpos) al ID definitivo de la
+/// macro llamada; los destinos que no existen se dejan como están.
+fn remap_calls(
+    step_ids: &[u64],
+    scope: Option<usize>,
+    steps: &mut AHashMap<u64, SequenceStep>,
+    step_bodies: &AHashMap<u64, Vec<Vec<u64>>>,
+    macro_ids: &MacroIds,
+) {
+    for sid in step_ids {
+        if let Some(SequenceStep::CallMacro { macro_id, .. }) = steps.get_mut(sid) {
+            if let Some(id) = macro_ids.resolve(scope, *macro_id) {
+                *macro_id = id;
+            }
+        }
+        for body in step_bodies.get(sid).into_iter().flatten() {
+            remap_calls(body, scope, steps, step_bodies, macro_ids);
+        }
+    }
+}
+
+/// Inserta la macro y sus pasos en las tablas y devuelve su ID definitivo.
+fn flatten_macro(
+    mac: &Macro,
+    macros: &mut AHashMap<u64, StoredMacro>,
+    steps: &mut AHashMap<u64, SequenceStep>,
+    step_bodies: &mut AHashMap<u64, Vec<Vec<u64>>>,
+    next_id: &mut u64,
+) -> u64 {
+    let sequence_step_ids = mac
+        .sequence
+        .iter()
+        .map(|step| flatten_step(step, steps, step_bodies, next_id))
+        .collect();
+
+    let mid = unique_id(macros, mac.id, next_id, "macro");
+    macros.insert(
+        mid,
+        StoredMacro {
+            id: mid,
+            name: mac.name.clone(),
+            trigger: mac.trigger.clone(),
+            sequence_step_ids,
+            script: mac.script.clone(),
+        },
+    );
+    mid
+}
+
+/// Inserta el paso con los cuerpos vacíos y sus hijos por separado, en `step_bodies`.
+fn flatten_step(
+    step: &SequenceStep,
+    steps: &mut AHashMap<u64, SequenceStep>,
+    step_bodies: &mut AHashMap<u64, Vec<Vec<u64>>>,
+    next_id: &mut u64,
+) -> u64 {
+    let sid = unique_id(steps, step.id(), next_id, "paso");
+    let mut step = step.clone();
+    step.set_id(sid);
+    let bodies = step
+        .bodies_mut()
+        .into_iter()
+        .map(std::mem::take)
+        .collect::<Vec<_>>();
+    // El padre ocupa su ID antes que los hijos para que no se lo quiten
+    steps.insert(sid, step);
 
-    for (_, stored_profile) in &storage.profiles {
-        let macros: Vec<Macro> = stored_profile
-            .macro_ids
+    if !bodies.is_empty() {
+        let children = bodies
             .iter()
-            .filter_map(|mid| storage.macros.get(mid))
-            .map(|stored_macro| Macro {
-                id: stored_macro.id,
-                name: stored_macro.name.clone(),
-                trigger: stored_macro.trigger.clone(),
-                sequence: stored_macro
-                    .sequence_step_ids
-                    .iter()
-                    .filter_map(|sid| storage.steps.get(sid).cloned())
-                    .collect(),
+            .map(|body| {
+                body.iter()
+                    .map(|child| flatten_step(child, steps, step_bodies, next_id))
+                    .collect()
             })
             .collect();
-
-        profiles.push(Profile {
-            id: stored_profile.id,
-            name: stored_profile.name.clone(),
-            function_key: stored_profile.function_key.clone(),
-            macros,
-        });
+        step_bodies.insert(sid, children);
     }
+    sid
+}
+
+#[inline]
+pub fn flat_to_config(storage: &FlatStorage) -> Config {
+    let profiles = storage
+        .ordered_profiles()
+        .into_iter()
+        .map(|stored_profile| stored_to_profile(storage, stored_profile))
+        .collect();
+
+    let library = storage
+        .library
+        .iter()
+        .filter_map(|id| storage.macros.get(id))
+        .map(|stored_macro| stored_to_macro(storage, stored_macro))
+        .collect();
 
     Config {
         profiles,
         selected_profile_id: storage.selected_profile_id,
+        library,
+        keyboard_layout: storage.keyboard_layout,
+        version: CONFIG_VERSION,
+        next_id: storage.next_id,
+    }
+}
+
+#[inline]
+pub fn stored_to_profile(storage: &FlatStorage, stored_profile: &StoredProfile) -> Profile {
+    let macros: Vec<Macro> = stored_profile
+        .macro_ids
+        .iter()
+        .filter_map(|mid| storage.macros.get(mid))
+        .map(|stored_macro| stored_to_macro(storage, stored_macro))
+        .collect();
+
+    Profile {
+        id: stored_profile.id,
+        name: stored_profile.name.clone(),
+        function_key: stored_profile.function_key.clone(),
+        macros,
+        persist_variables: stored_profile.persist_variables,
+    }
+}
+
+#[inline]
+pub fn stored_to_macro(storage: &FlatStorage, stored_macro: &StoredMacro) -> Macro {
+    Macro {
+        id: stored_macro.id,
+        name: stored_macro.name.clone(),
+        trigger: stored_macro.trigger.clone(),
+        sequence: storage.resolve_steps(&stored_macro.sequence_step_ids),
+        script: stored_macro.script.clone(),
+    }
+}
+
+fn max_id(config: &Config) -> u64 {
+    let macro_max = |m: &Macro| m.sequence.iter().map(step_max_id).fold(m.id, u64::max);
+
+    config
+        .profiles
+        .iter()
+        .flat_map(|p| std::iter::once(p.id).chain(p.macros.iter().map(macro_max)))
+        .chain(config.library.iter().map(macro_max))
+        .max()
+        .unwrap_or(0)
+}
+
+fn step_max_id(step: &SequenceStep) -> u64 {
+    step.bodies()
+        .into_iter()
+        .flatten()
+        .map(step_max_id)
+        .fold(step.id(), u64::max)
+}
+
+/// Devuelve `id` si está libre en la tabla o uno nuevo del contador si colisiona.
+#[inline]
+fn unique_id<V>(table: &AHashMap<u64, V>, id: u64, next_id: &mut u64, kind: &str) -> u64 {
+    if id != 0 && !table.contains_key(&id) {
+        return id;
     }
+
+    let new_id = *next_id;
+    *next_id += 1;
+    warn!("ID de {kind} {id} repetido, renumerado a {new_id}");
+    new_id
 }
 
-#[inline(always)]
-fn step_id(step: &SequenceStep) -> u64 {
-    match step {
-        SequenceStep::KeyDown { id, .. }
-        | SequenceStep::KeyUp { id, .. }
-        | SequenceStep::Delay { id, .. } => *id,
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::*;
+    use crate::domain::{
+        macros::KeyCombination,
+        sequence_step::{Condition, Timing},
+    };
+
+    fn step() -> impl Strategy<Value = SequenceStep> {
+        let leaf = prop_oneof![
+            "[A-Z]".prop_map(|key| SequenceStep::KeyDown { id: 0, key }),
+            "[A-Z]".prop_map(|key| SequenceStep::KeyUp { id: 0, key }),
+            (0u64..500).prop_map(|ms| SequenceStep::Delay {
+                id: 0,
+                ms: Timing::Fixed(ms)
+            }),
+            (1u64..100).prop_map(|macro_id| SequenceStep::CallMacro { id: 0, macro_id }),
+        ];
+
+        leaf.prop_recursive(3, 32, 4, |inner| {
+            prop_oneof![
+                (1u32..5, vec(inner.clone(), 0..4))
+                    .prop_map(|(count, body)| SequenceStep::Repeat { id: 0, count, body }),
+                vec(inner.clone(), 0..4)
+                    .prop_map(|body| SequenceStep::LoopUntilReleased { id: 0, body }),
+                (vec(inner.clone(), 0..4), vec(inner, 0..4)).prop_map(|(then, else_)| {
+                    SequenceStep::If {
+                        id: 0,
+                        condition: Condition::default(),
+                        then,
+                        else_,
+                    }
+                }),
+            ]
+        })
+    }
+
+    fn macro_def() -> impl Strategy<Value = Macro> {
+        (
+            "[a-z]{1,8}",
+            "[A-Z]",
+            vec(step(), 0..5),
+            prop::option::of("tap\\(\"[A-Z]\"\\);"),
+        )
+            .prop_map(|(name, key, sequence, script)| Macro {
+                id: 0,
+                name,
+                trigger: KeyCombination {
+                    modifiers: Vec::new(),
+                    key,
+                },
+                sequence,
+                script,
+            })
+    }
+
+    fn profile() -> impl Strategy<Value = Profile> {
+        (
+            "[a-z]{1,8}",
+            prop::option::of("F[1-9]"),
+            vec(macro_def(), 0..4),
+            any::<bool>(),
+        )
+            .prop_map(|(name, function_key, macros, persist_variables)| Profile {
+                id: 0,
+                name,
+                function_key,
+                macros,
+                persist_variables,
+            })
+    }
+
+    /// Configuración tal como la escribe el backend: IDs únicos y contador al día.
+    fn config() -> impl Strategy<Value = Config> {
+        (vec(profile(), 0..5), vec(macro_def(), 0..3), any::<bool>()).prop_map(
+            |(profiles, library, selected)| {
+                let mut config = Config {
+                    profiles,
+                    library,
+                    version: CONFIG_VERSION,
+                    ..Default::default()
+                };
+                let mut next_id = 1;
+                for profile in &mut config.profiles {
+                    profile.id = take_id(&mut next_id);
+                    for mac in &mut profile.macros {
+                        number_macro(mac, &mut next_id);
+                    }
+                }
+                for mac in &mut config.library {
+                    number_macro(mac, &mut next_id);
+                }
+                config.selected_profile_id =
+                    config.profiles.first().filter(|_| selected).map(|p| p.id);
+                config.next_id = next_id;
+                config
+            },
+        )
+    }
+
+    fn take_id(next_id: &mut u64) -> u64 {
+        let id = *next_id;
+        *next_id += 1;
+        id
+    }
+
+    fn number_macro(mac: &mut Macro, next_id: &mut u64) {
+        mac.id = take_id(next_id);
+        number_steps(&mut mac.sequence, next_id);
+    }
+
+    fn number_steps(steps: &mut [SequenceStep], next_id: &mut u64) {
+        for step in steps {
+            step.set_id(take_id(next_id));
+            for body in step.bodies_mut() {
+                number_steps(body, next_id);
+            }
+        }
+    }
+
+    fn legacy_macro(id: u64, sequence: Vec<SequenceStep>) -> Macro {
+        Macro {
+            id,
+            name: format!("macro {id}"),
+            trigger: KeyCombination::default(),
+            sequence,
+            script: None,
+        }
+    }
+
+    fn call(id: u64, macro_id: u64) -> SequenceStep {
+        SequenceStep::CallMacro { id, macro_id }
+    }
+
+    fn call_targets(mac: &Macro) -> Vec<u64> {
+        let mut targets = Vec::new();
+        crate::domain::sequence_step::visit_steps(&mac.sequence, &mut |step| {
+            if let SequenceStep::CallMacro { macro_id, .. } = step {
+                targets.push(*macro_id);
+            }
+        });
+        targets
+    }
+
+    /// Dos perfiles copiados con los mismos IDs, como los generaba el cliente.
+    fn legacy_profile(id: u64) -> Profile {
+        Profile {
+            id,
+            name: format!("perfil {id}"),
+            function_key: None,
+            macros: vec![
+                legacy_macro(
+                    5,
+                    vec![SequenceStep::Repeat {
+                        id: 50,
+                        count: 2,
+                        body: vec![call(51, 6)],
+                    }],
+                ),
+                legacy_macro(6, vec![call(60, 7)]),
+            ],
+            persist_variables: false,
+        }
+    }
+
+    #[test]
+    fn renumbered_macros_keep_calls_within_their_profile() {
+        let config = Config {
+            profiles: vec![legacy_profile(1), legacy_profile(1)],
+            library: vec![legacy_macro(7, Vec::new())],
+            selected_profile_id: Some(1),
+            ..Default::default()
+        };
+
+        let restored = flat_to_config(&config_to_flat(&config));
+        let [first, second] = &restored.profiles[..] else {
+            panic!("se esperaban dos perfiles");
+        };
+
+        assert_eq!(first.id, 1);
+        assert_ne!(second.id, 1);
+        assert_eq!(restored.selected_profile_id, Some(1));
+
+        assert_eq!(call_targets(&first.macros[0]), vec![6]);
+        let own = second.macros[1].id;
+        assert_ne!(own, 6);
+        assert_eq!(call_targets(&second.macros[0]), vec![own]);
+
+        // La biblioteca no está repetida: ambos perfiles la llaman igual
+        assert_eq!(call_targets(&first.macros[1]), vec![7]);
+        assert_eq!(call_targets(&second.macros[1]), vec![7]);
+    }
+
+    #[test]
+    fn calls_prefer_library_over_other_profiles() {
+        let mut caller = legacy_profile(2);
+        caller.macros = vec![legacy_macro(8, vec![call(80, 5)])];
+        let config = Config {
+            profiles: vec![legacy_profile(1), caller],
+            // Choca con la macro 5 del primer perfil y se renumera
+            library: vec![legacy_macro(5, Vec::new())],
+            ..Default::default()
+        };
+
+        let restored = flat_to_config(&config_to_flat(&config));
+        let library_id = restored.library[0].id;
+        assert_ne!(library_id, 5);
+        assert_eq!(
+            call_targets(&restored.profiles[1].macros[0]),
+            vec![library_id]
+        );
+    }
+
+    #[test]
+    fn ambiguous_calls_go_to_the_first_owner() {
+        let mut caller = legacy_profile(3);
+        caller.macros = vec![legacy_macro(9, vec![call(90, 5)])];
+        let config = Config {
+            profiles: vec![legacy_profile(1), legacy_profile(2), caller],
+            ..Default::default()
+        };
+
+        let restored = flat_to_config(&config_to_flat(&config));
+        assert_eq!(call_targets(&restored.profiles[2].macros[0]), vec![5]);
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip_preserves_config(config in config()) {
+            prop_assert_eq!(flat_to_config(&config_to_flat(&config)), config);
+        }
+
+        #[test]
+        fn reordered_profiles_survive_round_trip(config in config(), seed in any::<u64>()) {
+            let mut flat = config_to_flat(&config);
+            let mut order = flat.profile_order.clone();
+            let shift = (seed as usize) % order.len().max(1);
+            order.rotate_left(shift);
+            flat.reorder_profiles(order.clone()).unwrap();
+
+            let restored = config_to_flat(&flat_to_config(&flat));
+            prop_assert_eq!(restored.profile_order, order);
+        }
     }
 }
@@ -0,0 +1,147 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::{Context, Result};
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+use crate::config::{
+    handler::{latest_corrupt_file, save_to_disk},
+    model::FlatStorage,
+};
+
+pub(crate) const CONFIG_FILE_NAME: &str = "config.json";
+/// Si en el directorio ya hay una configuración en otro formato, se usa esa.
+const CONFIG_FILE_CANDIDATES: [&str; 4] =
+    [CONFIG_FILE_NAME, "config.toml", "config.yaml", "config.yml"];
+pub(crate) const CONFIG_ENV_VAR: &str = "FIGHT_MACROS_CONFIG";
+pub(crate) const CONFIG_CLI_FLAG: &str = "--config";
+
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Resuelve la ruta del archivo de configuración una sola vez al iniciar.
+///
+/// Prioridad: `--config <ruta>`, luego `FIGHT_MACROS_CONFIG` y por último el
+/// directorio de configuración de la aplicación (XDG en Linux). El formato
+/// (JSON, TOML o YAML) lo decide la extensión.
+pub fn init_config_path(app: &AppHandle) -> Result<&'static Path> {
+    let path = match override_path() {
+        Some(path) => {
+            info!(
+                "Usando configuración indicada por el usuario: {}",
+                path.display()
+            );
+            path
+        }
+        None => {
+            let dir = app
+                .path()
+                .app_config_dir()
+                .context("No se pudo resolver el directorio de configuración")?;
+            let path = CONFIG_FILE_CANDIDATES
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|p| p.is_file())
+                .unwrap_or_else(|| dir.join(CONFIG_FILE_NAME));
+            migrate_legacy_config(&path)?;
+            path
+        }
+    };
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("No se pudo crear {}", parent.display()))?;
+    }
+
+    let path = CONFIG_PATH.get_or_init(|| path);
+
+    // El watchdog necesita que el archivo exista
+    if !path.exists() {
+        // Con una copia dañada apartada, una configuración vacía sacaría al
+        // usuario del modo recuperación sin avisar
+        if let Some(corrupt) = latest_corrupt_file() {
+            warn!(
+                "No se crea {}: queda una configuración dañada en {}",
+                path.display(),
+                corrupt.display()
+            );
+        } else {
+            save_to_disk(&FlatStorage::default())
+                .context("No se pudo crear el archivo de configuración")?;
+        }
+    }
+
+    Ok(path)
+}
+
+pub fn config_path() -> &'static Path {
+    CONFIG_PATH
+        .get()
+        .expect("Ruta de configuración no inicializada")
+}
+
+/// Archivo auxiliar junto a la configuración: `<nombre>.<suffix>`.
+pub fn sibling_path(suffix: &str) -> PathBuf {
+    let path = config_path();
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    path.with_file_name(format!("{stem}.{suffix}"))
+}
+
+fn override_path() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == CONFIG_CLI_FLAG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(value) = arg
+            .strip_prefix(CONFIG_CLI_FLAG)
+            .and_then(|a| a.strip_prefix('='))
+        {
+            return Some(PathBuf::from(value));
+        }
+    }
+
+    std::env::var_os(CONFIG_ENV_VAR)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Mueve un `./config.json` de versiones anteriores al nuevo directorio.
+fn migrate_legacy_config(target: &Path) -> Result<()> {
+    let legacy = Path::new(CONFIG_FILE_NAME);
+    if target.exists() || !legacy.is_file() {
+        return Ok(());
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("No se pudo crear {}", parent.display()))?;
+    }
+
+    // `rename` falla entre sistemas de archivos distintos
+    if fs::rename(legacy, target).is_err() {
+        fs::copy(legacy, target).with_context(|| {
+            format!(
+                "No se pudo migrar {} a {}",
+                legacy.display(),
+                target.display()
+            )
+        })?;
+        if let Err(e) = fs::remove_file(legacy) {
+            warn!("No se pudo eliminar {}: {e}", legacy.display());
+        }
+    }
+
+    info!(
+        "Configuración migrada de {} a {}",
+        legacy.display(),
+        target.display()
+    );
+    Ok(())
+}
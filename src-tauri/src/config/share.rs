@@ -0,0 +1,403 @@
+use std::{collections::VecDeque, fs, path::Path};
+
+use ahash::{AHashMap, AHashSet};
+use anyhow::{bail, Context, Result};
+use tracing::info;
+
+use crate::{
+    config::{
+        handler::{self, get_config, write_atomic},
+        model::FlatStorage,
+        parser,
+    },
+    domain::{
+        config::Revisioned,
+        macros::{KeyCombination, Macro},
+        sequence_step::{visit_steps, SequenceStep},
+        share::{
+            ImportReport, SharedContent, SharedFile, TriggerConflict, SHARED_FORMAT, SHARED_VERSION,
+        },
+    },
+    keys,
+};
+
+pub fn export_profile(id: u64, path: &Path) -> Result<()> {
+    let cfg = get_config();
+    let profile = cfg
+        .find_profile(&id)
+        .with_context(|| format!("Perfil {id} no encontrado"))?;
+
+    let profile = parser::stored_to_profile(&cfg, profile);
+    let library = called_macros(&cfg, &profile.macros)?;
+    write(SharedContent::Profile(profile), library, path)
+}
+
+pub fn export_macro(id: u64, path: &Path) -> Result<()> {
+    let cfg = get_config();
+    let mac = cfg
+        .find_macro(&id)
+        .with_context(|| format!("Macro {id} no encontrada"))?;
+
+    let mac = parser::stored_to_macro(&cfg, mac);
+    let library = called_macros(&cfg, std::slice::from_ref(&mac))?;
+    write(SharedContent::Macro(mac), library, path)
+}
+
+/// Macros que llaman los pasos de `macros`, directa o indirectamente, y que no
+/// están ya entre ellas; así el archivo no depende de la configuración de origen.
+fn called_macros(cfg: &FlatStorage, macros: &[Macro]) -> Result<Vec<Macro>> {
+    let mut seen = macros.iter().map(|m| m.id).collect::<AHashSet<_>>();
+    let mut pending = macros
+        .iter()
+        .flat_map(|m| calls(&m.sequence))
+        .collect::<VecDeque<_>>();
+
+    let mut library = Vec::new();
+    while let Some(id) = pending.pop_front() {
+        if !seen.insert(id) {
+            continue;
+        }
+        let mac = cfg
+            .find_macro(&id)
+            .with_context(|| format!("Se llama a la macro {id}, que no existe"))?;
+        let mac = parser::stored_to_macro(cfg, mac);
+        pending.extend(calls(&mac.sequence));
+        library.push(mac);
+    }
+    Ok(library)
+}
+
+fn calls(steps: &[SequenceStep]) -> Vec<u64> {
+    let mut ids = Vec::new();
+    visit_steps(steps, &mut |step| {
+        if let SequenceStep::CallMacro { macro_id, .. } = step {
+            ids.push(*macro_id);
+        }
+    });
+    ids
+}
+
+fn write(content: SharedContent, library: Vec<Macro>, path: &Path) -> Result<()> {
+    let file = SharedFile {
+        format: SHARED_FORMAT.to_string(),
+        version: SHARED_VERSION,
+        content,
+        library,
+    };
+    let json = serde_json::to_vec_pretty(&file).context("No se pudo serializar la exportación")?;
+
+    write_atomic(path, &json)?;
+    info!("Exportado a {}", path.display());
+    Ok(())
+}
+
+pub fn read(path: &Path) -> Result<SharedFile> {
+    let bytes = fs::read(path).with_context(|| format!("No se pudo leer {}", path.display()))?;
+    let file = serde_json::from_slice::<SharedFile>(&bytes)
+        .with_context(|| format!("{} no es un archivo de intercambio válido", path.display()))?;
+
+    if file.format != SHARED_FORMAT {
+        bail!("Formato desconocido: {}", file.format);
+    }
+    if file.version > SHARED_VERSION {
+        bail!(
+            "El archivo usa la versión {} y solo se admite hasta la {SHARED_VERSION}",
+            file.version
+        );
+    }
+    Ok(file)
+}
+
+/// Importa un archivo con IDs nuevos.
+///
+/// Un perfil sin `target_profile_id` se crea como perfil nuevo; con él, sus macros
+/// se añaden al perfil indicado. Una macro suelta siempre necesita perfil destino.
+/// Las macros llamadas que trae el archivo se crean en la biblioteca.
+pub fn import(
+    expected_revision: u64,
+    path: &Path,
+    target_profile_id: Option<u64>,
+) -> Result<Revisioned<ImportReport>> {
+    let file = read(path)?;
+    let description = match &file.content {
+        SharedContent::Profile(p) => format!("Importar perfil {}", p.name),
+        SharedContent::Macro(m) => format!("Importar macro {}", m.name),
+    };
+
+    handler::mutate_config(expected_revision, &description, |cfg| {
+        import_into(cfg, file, target_profile_id)
+    })
+}
+
+fn import_into(
+    cfg: &mut FlatStorage,
+    file: SharedFile,
+    target_profile_id: Option<u64>,
+) -> Result<ImportReport> {
+    let (macros, profile_id, function_key_dropped) = match (file.content, target_profile_id) {
+        (SharedContent::Profile(profile), Some(id)) => {
+            cfg.find_profile(&id)
+                .with_context(|| format!("Perfil {id} no encontrado"))?;
+            (profile.macros, id, false)
+        }
+        (SharedContent::Profile(profile), None) => {
+            // Dos perfiles con la misma tecla de función serían ambiguos
+            let taken = profile.function_key.as_ref().is_some_and(|key| {
+                cfg.profiles
+                    .values()
+                    .any(|p| p.function_key.as_ref() == Some(key))
+            });
+            let function_key = profile.function_key.filter(|_| !taken);
+            let id = cfg.create_profile(profile.name, function_key);
+            (profile.macros, id, taken)
+        }
+        (SharedContent::Macro(mac), Some(id)) => (vec![mac], id, false),
+        (SharedContent::Macro(_), None) => {
+            bail!("Indica el perfil destino para importar una macro")
+        }
+    };
+
+    let existing = cfg
+        .find_profile(&profile_id)
+        .with_context(|| format!("Perfil {profile_id} no encontrado"))?
+        .macro_ids
+        .iter()
+        .filter_map(|id| cfg.macros.get(id))
+        .map(|m| (m.id, m.trigger.clone()))
+        .collect::<Vec<_>>();
+
+    let mut report = ImportReport {
+        profile_id,
+        macro_ids: Vec::with_capacity(macros.len()),
+        library_macro_ids: Vec::with_capacity(file.library.len()),
+        conflicts: Vec::new(),
+        function_key_dropped,
+    };
+
+    // Primero se crean las macros vacías para conocer todos los IDs nuevos
+    // antes de apuntar las llamadas a ellos
+    let mut ids = AHashMap::with_capacity(macros.len() + file.library.len());
+    let mut created = Vec::with_capacity(macros.len() + file.library.len());
+    for mac in macros {
+        let id = cfg.create_macro(
+            profile_id,
+            mac.name.clone(),
+            mac.trigger.clone(),
+            Vec::new(),
+        )?;
+        if let Some((existing_id, _)) = existing
+            .iter()
+            .find(|(_, trigger)| same_trigger(trigger, &mac.trigger))
+        {
+            report.conflicts.push(TriggerConflict {
+                imported_macro_id: id,
+                existing_macro_id: *existing_id,
+                trigger: mac.trigger.clone(),
+            });
+        }
+        ids.insert(mac.id, id);
+        report.macro_ids.push(id);
+        created.push((id, mac));
+    }
+    for mac in file.library {
+        let id = cfg.create_library_macro(mac.name.clone(), Vec::new())?;
+        ids.insert(mac.id, id);
+        report.library_macro_ids.push(id);
+        created.push((id, mac));
+    }
+
+    for (id, mut mac) in created {
+        remap_calls(&mut mac.sequence, &ids)?;
+        for step in mac.sequence {
+            cfg.create_step(id, step, None)?;
+        }
+        cfg.set_macro_script(id, mac.script)?;
+    }
+
+    Ok(report)
+}
+
+/// Apunta las llamadas a los IDs nuevos. Llamar a una macro que no viene en el
+/// archivo se rechaza para no acabar llamando a otra local con el mismo ID.
+fn remap_calls(steps: &mut [SequenceStep], ids: &AHashMap<u64, u64>) -> Result<()> {
+    for step in steps {
+        if let SequenceStep::CallMacro { macro_id, .. } = step {
+            *macro_id = *ids.get(macro_id).with_context(|| {
+                format!("Se llama a la macro {macro_id}, que no viene en el archivo")
+            })?;
+        }
+        for body in step.bodies_mut() {
+            remap_calls(body, ids)?;
+        }
+    }
+    Ok(())
+}
+
+/// Compara disparadores por teclas o botones reales, sin importar el orden de
+/// los modificadores.
+fn same_trigger(a: &KeyCombination, b: &KeyCombination) -> bool {
+    let modifiers = |c: &KeyCombination| {
+        c.modifiers
+            .iter()
+            .map(keys::mod_to_key)
+            .collect::<AHashSet<_>>()
+    };
+
+    keys::str_to_input(&a.key) == keys::str_to_input(&b.key) && modifiers(a) == modifiers(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{enums::ModifierKey, profiles::Profile};
+
+    fn trigger(key: &str, modifiers: Vec<ModifierKey>) -> KeyCombination {
+        KeyCombination {
+            modifiers,
+            key: key.into(),
+        }
+    }
+
+    fn call(macro_id: u64) -> SequenceStep {
+        SequenceStep::CallMacro { id: 0, macro_id }
+    }
+
+    fn mac(id: u64, key: &str, sequence: Vec<SequenceStep>) -> Macro {
+        Macro {
+            id,
+            name: format!("macro {id}"),
+            trigger: trigger(key, Vec::new()),
+            sequence,
+            script: None,
+        }
+    }
+
+    fn shared(content: SharedContent, library: Vec<Macro>) -> SharedFile {
+        SharedFile {
+            format: SHARED_FORMAT.to_string(),
+            version: SHARED_VERSION,
+            content,
+            library,
+        }
+    }
+
+    /// IDs de las macros que llaman los pasos de `id`, anidados incluidos.
+    fn targets(cfg: &FlatStorage, id: u64) -> Vec<u64> {
+        calls(&cfg.macro_sequence(id).unwrap())
+    }
+
+    #[test]
+    fn import_remaps_calls_and_bundled_library() {
+        let mut cfg = FlatStorage::default();
+        // Ocupa los IDs del archivo para que reutilizarlos fuera un error visible
+        let local = cfg.create_profile("local".into(), None);
+        let decoy = cfg
+            .create_macro(
+                local,
+                "señuelo".into(),
+                trigger("Z", Vec::new()),
+                Vec::new(),
+            )
+            .unwrap();
+
+        let file = shared(
+            SharedContent::Profile(Profile {
+                id: 1,
+                name: "importado".into(),
+                function_key: None,
+                macros: vec![mac(
+                    decoy,
+                    "A",
+                    vec![SequenceStep::Repeat {
+                        id: 0,
+                        count: 2,
+                        body: vec![call(20)],
+                    }],
+                )],
+                persist_variables: false,
+            }),
+            vec![mac(20, "", vec![call(30)]), mac(30, "", Vec::new())],
+        );
+
+        let report = import_into(&mut cfg, file, None).unwrap();
+
+        let [imported] = report.macro_ids[..] else {
+            panic!("se esperaba una macro importada: {report:?}");
+        };
+        let [first, second] = report.library_macro_ids[..] else {
+            panic!("se esperaban dos macros de biblioteca: {report:?}");
+        };
+        assert_ne!(imported, decoy);
+        assert!([first, second]
+            .iter()
+            .all(|id| ![20, 30, decoy].contains(id)));
+        assert_eq!(targets(&cfg, imported), vec![first]);
+        assert_eq!(targets(&cfg, first), vec![second]);
+        assert!(cfg.library.contains(&first) && cfg.library.contains(&second));
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn import_rejects_calls_missing_from_the_file() {
+        let mut cfg = FlatStorage::default();
+        let profile = cfg.create_profile("local".into(), None);
+        let file = shared(
+            SharedContent::Macro(mac(1, "A", vec![call(99)])),
+            Vec::new(),
+        );
+
+        assert!(import_into(&mut cfg, file, Some(profile)).is_err());
+    }
+
+    #[test]
+    fn import_reports_trigger_conflicts() {
+        let mut cfg = FlatStorage::default();
+        let profile = cfg.create_profile("local".into(), None);
+        let existing = cfg
+            .create_macro(
+                profile,
+                "existente".into(),
+                trigger("A", vec![ModifierKey::Ctrl, ModifierKey::Shift]),
+                Vec::new(),
+            )
+            .unwrap();
+
+        let mut conflicting = mac(1, "a", Vec::new());
+        conflicting.trigger.modifiers = vec![ModifierKey::Shift, ModifierKey::Control];
+        let file = shared(SharedContent::Macro(conflicting), Vec::new());
+        let report = import_into(&mut cfg, file, Some(profile)).unwrap();
+
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].existing_macro_id, existing);
+        assert_eq!(report.conflicts[0].imported_macro_id, report.macro_ids[0]);
+
+        let file = shared(SharedContent::Macro(mac(1, "A", Vec::new())), Vec::new());
+        let report = import_into(&mut cfg, file, Some(profile)).unwrap();
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn same_trigger_compares_real_inputs() {
+        let cases = [
+            (trigger("A", vec![]), trigger("A", vec![]), true),
+            (trigger("A", vec![]), trigger("B", vec![]), false),
+            (
+                trigger("A", vec![ModifierKey::Ctrl, ModifierKey::Alt]),
+                trigger("A", vec![ModifierKey::Alt, ModifierKey::Control]),
+                true,
+            ),
+            (
+                trigger("A", vec![ModifierKey::Shift]),
+                trigger("A", vec![]),
+                false,
+            ),
+            (trigger("MOUSE4", vec![]), trigger("MOUSE4", vec![]), true),
+            (trigger("MOUSE4", vec![]), trigger("MOUSE5", vec![]), false),
+            (trigger("MOUSE1", vec![]), trigger("A", vec![]), false),
+        ];
+
+        for (a, b, expected) in cases {
+            assert_eq!(same_trigger(&a, &b), expected, "{a:?} vs {b:?}");
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use ahash::AHashMap;
+
+use crate::{
+    config::model::FlatStorage,
+    domain::{
+        sequence_step::{nesting_depth, visit_steps, Condition, SequenceStep, Timing},
+        validation::ValidationIssue,
+    },
+    engine::script,
+    keys,
+};
+
+/// Máximo de llamadas `CallMacro` anidadas. El ejecutor aplica el mismo límite
+/// por si la configuración cambia entre la validación y la ejecución.
+pub const MAX_CALL_DEPTH: usize = 8;
+
+/// Máximo de pasos compuestos anidados dentro de una macro.
+pub const MAX_STEP_DEPTH: usize = 16;
+
+/// Máximo de condiciones anidadas en un paso `If`.
+pub const MAX_CONDITION_DEPTH: usize = 8;
+
+#[derive(Clone, Copy)]
+enum Visit {
+    InProgress,
+    /// Profundidad de llamadas ya calculada.
+    Done(usize),
+}
+
+/// Revisa las referencias entre macros (destinos inexistentes, ciclos y
+/// anidamiento por encima de [`MAX_CALL_DEPTH`]), el anidamiento de pasos
+/// y los pasos y scripts que no se pueden ejecutar tal cual.
+pub fn validate(storage: &FlatStorage) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut visits = AHashMap::new();
+
+    let mut ids = storage.macros.keys().copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+
+    for id in ids {
+        if let Some(Err(e)) = storage.macros[&id].script.as_deref().map(script::check) {
+            issues.push(ValidationIssue {
+                macro_id: id,
+                step_id: None,
+                message: format!("El script no compila: {e}"),
+            });
+        }
+
+        for (step_id, message) in step_issues(storage, id) {
+            issues.push(ValidationIssue {
+                macro_id: id,
+                step_id: Some(step_id),
+                message,
+            });
+        }
+
+        let nesting = nesting_depth(&storage.macro_sequence(id).unwrap_or_default());
+        if nesting > MAX_STEP_DEPTH {
+            issues.push(ValidationIssue {
+                macro_id: id,
+                step_id: None,
+                message: format!(
+                    "La macro anida {nesting} niveles de pasos y el máximo es {MAX_STEP_DEPTH}"
+                ),
+            });
+        }
+
+        let depth = visit(storage, id, &mut visits, &mut Vec::new(), &mut issues);
+        if depth > MAX_CALL_DEPTH {
+            issues.push(ValidationIssue {
+                macro_id: id,
+                step_id: None,
+                message: format!("La macro anida {depth} llamadas y el máximo es {MAX_CALL_DEPTH}"),
+            });
+        }
+    }
+
+    issues
+}
+
+/// Profundidad de llamadas de la macro; un ciclo se informa una vez y cuenta como 0.
+fn visit(
+    storage: &FlatStorage,
+    id: u64,
+    visits: &mut AHashMap<u64, Visit>,
+    path: &mut Vec<u64>,
+    issues: &mut Vec<ValidationIssue>,
+) -> usize {
+    match visits.get(&id) {
+        Some(Visit::Done(depth)) => return *depth,
+        Some(Visit::InProgress) => return 0,
+        None => {}
+    }
+
+    visits.insert(id, Visit::InProgress);
+    path.push(id);
+
+    let mut depth = 0;
+    for (step_id, target) in calls(storage, id) {
+        if !storage.macros.contains_key(&target) {
+            issues.push(ValidationIssue {
+                macro_id: id,
+                step_id: Some(step_id),
+                message: format!("Llama a la macro {target}, que no existe"),
+            });
+            continue;
+        }
+
+        if let Some(Visit::InProgress) = visits.get(&target) {
+            let start = path.iter().position(|m| *m == target).unwrap_or(0);
+            let cycle = path[start..]
+                .iter()
+                .chain(std::iter::once(&target))
+                .map(|m| macro_name(storage, *m))
+                .collect::<Vec<_>>()
+                .join(" → ");
+            issues.push(ValidationIssue {
+                macro_id: id,
+                step_id: Some(step_id),
+                message: format!("Ciclo de llamadas: {cycle}"),
+            });
+            continue;
+        }
+
+        depth = depth.max(1 + visit(storage, target, visits, path, issues));
+    }
+
+    path.pop();
+    visits.insert(id, Visit::Done(depth));
+    depth
+}
+
+/// Pasos `CallMacro` de la macro, incluidos los anidados, como pares (paso, macro destino).
+fn calls(storage: &FlatStorage, id: u64) -> Vec<(u64, u64)> {
+    let sequence = storage.macro_sequence(id).unwrap_or_default();
+    let mut out = Vec::new();
+    visit_steps(&sequence, &mut |step| {
+        if let SequenceStep::CallMacro { id, macro_id } = step {
+            out.push((*id, *macro_id));
+        }
+    });
+    out
+}
+
+/// Problemas de pasos sueltos: bucles sin cuerpo (se repetirían en vacío
+/// mientras la tecla siga pulsada), duraciones con el rango invertido,
+/// acordes sin teclas, botones del ratón desconocidos, texto con caracteres
+/// que la distribución configurada no puede escribir, condiciones demasiado
+/// anidadas o con teclas desconocidas, variables sin nombre, contadores con
+/// un `wrap` que no es positivo y scripts que no compilan.
+fn step_issues(storage: &FlatStorage, id: u64) -> Vec<(u64, String)> {
+    let sequence = storage.macro_sequence(id).unwrap_or_default();
+    let layout = storage.keyboard_layout;
+    let mut out = Vec::new();
+    visit_steps(&sequence, &mut |step| match step {
+        SequenceStep::LoopUntilReleased { id, body } if body.is_empty() => {
+            out.push((*id, "El bucle no tiene pasos".to_string()));
+        }
+        SequenceStep::Chord { id, keys, .. } if keys.is_empty() => {
+            out.push((*id, "El acorde no tiene teclas".to_string()));
+        }
+        SequenceStep::Delay { id, ms: timing }
+        | SequenceStep::Tap {
+            id,
+            hold_ms: timing,
+            ..
+        }
+        | SequenceStep::Chord {
+            id,
+            hold_ms: timing,
+            ..
+        } if inverted_bounds(timing) => {
+            out.push((
+                *id,
+                "El mínimo de la duración es mayor que el máximo".to_string(),
+            ));
+        }
+        SequenceStep::MouseDown { id, button }
+        | SequenceStep::MouseUp { id, button }
+        | SequenceStep::MouseClick { id, button }
+            if keys::str_to_button(button).is_none() =>
+        {
+            out.push((*id, format!("Botón del ratón desconocido: {button}")));
+        }
+        SequenceStep::TypeText { id, text, .. } => {
+            let mut missing = String::new();
+            for c in text.chars() {
+                if keys::char_to_key(c, layout).is_none() && !missing.contains(c) {
+                    missing.push(c);
+                }
+            }
+            if !missing.is_empty() {
+                out.push((
+                    *id,
+                    format!("No se pueden escribir {missing:?} con la distribución {layout}"),
+                ));
+            }
+        }
+        SequenceStep::If { id, condition, .. } => {
+            let depth = condition.depth();
+            if depth > MAX_CONDITION_DEPTH {
+                out.push((
+                    *id,
+                    format!(
+                        "La condición anida {depth} niveles y el máximo es {MAX_CONDITION_DEPTH}"
+                    ),
+                ));
+            }
+            condition.visit(&mut |c| match c {
+                Condition::Held { key }
+                    if keys::str_to_input(key) == keys::InputKey::Key(rdev::Key::Unknown(0)) =>
+                {
+                    out.push((*id, format!("Tecla desconocida en la condición: {key}")));
+                }
+                Condition::Var { name, .. } if name.trim().is_empty() => {
+                    out.push((*id, "La condición usa una variable sin nombre".to_string()));
+                }
+                _ => {}
+            });
+        }
+        SequenceStep::SetVar { id, name, .. } | SequenceStep::IncrementVar { id, name, .. }
+            if name.trim().is_empty() =>
+        {
+            out.push((*id, "La variable no tiene nombre".to_string()));
+        }
+        SequenceStep::IncrementVar {
+            id,
+            wrap: Some(wrap),
+            ..
+        } if *wrap <= 0 => {
+            out.push((
+                *id,
+                format!("El ciclo del contador debe ser positivo ({wrap})"),
+            ));
+        }
+        SequenceStep::Script { id, source } => {
+            if let Err(e) = script::check(source) {
+                out.push((*id, format!("El script no compila: {e}")));
+            }
+        }
+        _ => {}
+    });
+    out
+}
+
+fn inverted_bounds(timing: &Timing) -> bool {
+    match *timing {
+        Timing::Fixed(_) => false,
+        Timing::Uniform { min, max } => min > max,
+        Timing::Normal { min, max, .. } => min.zip(max).is_some_and(|(min, max)| min > max),
+    }
+}
+
+fn macro_name(storage: &FlatStorage, id: u64) -> String {
+    storage
+        .macros
+        .get(&id)
+        .map_or_else(|| id.to_string(), |m| m.name.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::parser::config_to_flat,
+        domain::{
+            config::Config,
+            macros::{KeyCombination, Macro},
+        },
+    };
+
+    fn mac(id: u64, sequence: Vec<SequenceStep>) -> Macro {
+        Macro {
+            id,
+            name: format!("m{id}"),
+            trigger: KeyCombination::default(),
+            sequence,
+            script: None,
+        }
+    }
+
+    /// Paso con un ID que no choque con las macros (todas por debajo de 100).
+    fn call(macro_id: u64) -> SequenceStep {
+        SequenceStep::CallMacro {
+            id: 1000 + macro_id,
+            macro_id,
+        }
+    }
+
+    fn issues(library: Vec<Macro>) -> Vec<ValidationIssue> {
+        validate(&config_to_flat(&Config {
+            library,
+            ..Default::default()
+        }))
+    }
+
+    fn messages(library: Vec<Macro>) -> Vec<String> {
+        issues(library).into_iter().map(|i| i.message).collect()
+    }
+
+    /// Cadena 1 → 2 → … → `len`.
+    fn chain(len: u64) -> Vec<Macro> {
+        (1..=len)
+            .map(|id| {
+                let sequence = if id < len {
+                    vec![call(id + 1)]
+                } else {
+                    Vec::new()
+                };
+                mac(id, sequence)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn valid_calls_have_no_issues() {
+        assert!(issues(chain(3)).is_empty());
+    }
+
+    #[test]
+    fn reports_missing_targets() {
+        let found = issues(vec![mac(1, vec![call(42)])]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].macro_id, 1);
+        assert_eq!(found[0].step_id, Some(1042));
+        assert!(found[0].message.contains("42"), "{}", found[0].message);
+    }
+
+    #[test]
+    fn reports_cycles_once() {
+        let found = messages(vec![
+            mac(1, vec![call(2)]),
+            mac(2, vec![call(3)]),
+            mac(3, vec![call(1)]),
+        ]);
+        assert_eq!(found, vec!["Ciclo de llamadas: m1 → m2 → m3 → m1"]);
+
+        let found = messages(vec![mac(1, vec![call(1)])]);
+        assert_eq!(found, vec!["Ciclo de llamadas: m1 → m1"]);
+    }
+
+    #[test]
+    fn limits_call_depth() {
+        let depth = MAX_CALL_DEPTH as u64;
+        assert!(issues(chain(depth + 1)).is_empty());
+
+        let found = issues(chain(depth + 2));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].macro_id, 1);
+        assert!(found[0].message.contains("anida"), "{}", found[0].message);
+    }
+
+    #[test]
+    fn limits_step_nesting() {
+        fn nested(levels: usize) -> Vec<SequenceStep> {
+            (0..levels).fold(Vec::new(), |body, i| {
+                vec![SequenceStep::Repeat {
+                    id: 100 + i as u64,
+                    count: 1,
+                    body,
+                }]
+            })
+        }
+
+        // Los compuestos cuentan un nivel más que la secuencia que los contiene
+        assert!(issues(vec![mac(1, nested(MAX_STEP_DEPTH - 1))]).is_empty());
+        let found = messages(vec![mac(1, nested(MAX_STEP_DEPTH))]);
+        assert_eq!(found.len(), 1);
+        assert!(found[0].contains("niveles de pasos"), "{}", found[0]);
+    }
+
+    #[test]
+    fn limits_condition_depth() {
+        fn step(levels: usize) -> SequenceStep {
+            let condition = (1..levels).fold(
+                Condition::Facing {
+                    side: Default::default(),
+                },
+                |c, _| Condition::Not {
+                    condition: Box::new(c),
+                },
+            );
+            SequenceStep::If {
+                id: 100,
+                condition,
+                then: Vec::new(),
+                else_: Vec::new(),
+            }
+        }
+
+        assert!(issues(vec![mac(1, vec![step(MAX_CONDITION_DEPTH)])]).is_empty());
+        let found = issues(vec![mac(1, vec![step(MAX_CONDITION_DEPTH + 1)])]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].step_id, Some(100));
+        assert!(
+            found[0].message.contains("condición"),
+            "{}",
+            found[0].message
+        );
+    }
+
+    #[test]
+    fn reports_scripts_that_do_not_compile() {
+        let mut whole = mac(1, Vec::new());
+        whole.script = Some("let x = ;".into());
+        let step = mac(
+            2,
+            vec![SequenceStep::Script {
+                id: 100,
+                source: "tap(".into(),
+            }],
+        );
+        let mut valid = mac(3, Vec::new());
+        valid.script = Some("let x = 1;".into());
+
+        let found = issues(vec![whole, step, valid]);
+        assert_eq!(found.len(), 2, "{found:?}");
+        assert_eq!((found[0].macro_id, found[0].step_id), (1, None));
+        assert_eq!((found[1].macro_id, found[1].step_id), (2, Some(100)));
+        assert!(found
+            .iter()
+            .all(|i| i.message.starts_with("El script no compila")));
+    }
+}
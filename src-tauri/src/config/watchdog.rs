@@ -1,11 +1,24 @@
-use std::path::Path;
+use std::{fs, path::Path, time::Duration};
 
-use anyhow::Result;
-use crossbeam_channel::Sender;
-use notify::{RecursiveMode, Watcher};
-use tracing::{error, info};
+use anyhow::{Context, Result};
+use crossbeam_channel::{Receiver, Sender};
+use notify::{Event, RecursiveMode, Watcher};
+use tauri::Emitter;
+use tracing::{debug, error, info};
 
-use crate::config::handler::{load_from_disk, CONFIG, CONFIG_PATH};
+use crate::{
+    application::handler::try_get_app_handle,
+    config::{
+        handler::{
+            enter_recovery, is_known_content, leave_recovery, load_from_bytes, replace_config,
+        },
+        path::config_path,
+    },
+    domain::enums::ListenableChannel,
+};
+
+/// Tiempo sin eventos que se espera antes de recargar.
+const DEBOUNCE: Duration = Duration::from_millis(250);
 
 #[derive(Debug)]
 pub struct ConfigWatchdog {
@@ -23,9 +36,16 @@ impl ConfigWatchdog {
             let _ = tx.send(res);
         })?;
 
+        // Se observa el directorio: el rename atómico de `save_to_disk`
+        // reemplaza el inodo y rompería un watch sobre el archivo.
+        let path = config_path();
+        let dir = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
         watcher
-            .watch(Path::new(CONFIG_PATH), RecursiveMode::NonRecursive)
-            .map_err(|e| anyhow::anyhow!("Error al observar config.json: {e:?}"))?;
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| anyhow::anyhow!("Error al observar {}: {e:?}", dir.display()))?;
 
         let thread = std::thread::Builder::new()
             .name("config_watchdog".into())
@@ -34,14 +54,13 @@ impl ConfigWatchdog {
                     crossbeam::select! {
                         recv(rx) -> event => {
                             match event {
-                                Ok(_) => match load_from_disk() {
-                                    Ok(new) => {
-                                        *CONFIG.write() = new;
-                                        info!("[INFO] config.json recargado");
-                                    }
-                                    Err(e) => error!("[ERROR] No se pudo recargar config.json: {:?}", e),
-                                },
-                                Err(e) => error!("[ERROR] Error al observar config.json: {:?}", e),
+                                Ok(Ok(ev)) if Self::concerns(&ev, path) => {
+                                    Self::wait_quiet(&rx);
+                                    Self::reload(path);
+                                }
+                                Ok(Ok(_)) => {}
+                                Ok(Err(e)) => error!("[ERROR] Error al observar {}: {:?}", path.display(), e),
+                                Err(_) => break,
                             }
                         }
                         recv(shutdown_rx) -> _ => break,
@@ -56,6 +75,55 @@ impl ConfigWatchdog {
         })
     }
 
+    #[inline]
+    fn concerns(ev: &Event, path: &Path) -> bool {
+        ev.paths.iter().any(|p| p.file_name() == path.file_name())
+    }
+
+    /// Agrupa ráfagas de eventos (tmp + rename, guardados de editores).
+    fn wait_quiet(rx: &Receiver<notify::Result<Event>>) {
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+    }
+
+    fn reload(path: &Path) {
+        let bytes = match fs::read(path).context("No se pudo leer el archivo de configuración") {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                error!("[ERROR] No se pudo recargar {}: {:?}", path.display(), e);
+                return;
+            }
+        };
+
+        if is_known_content(&bytes) {
+            debug!("Evento propio o sin cambios, se ignora");
+            return;
+        }
+
+        match load_from_bytes(&bytes) {
+            Ok(new) => {
+                let changes = replace_config(new);
+                leave_recovery();
+                info!("[INFO] {} recargado: {:?}", path.display(), changes);
+
+                if changes.is_empty() {
+                    return;
+                }
+                if let Some(handle) = try_get_app_handle() {
+                    let channel = &ListenableChannel::ConfigReloaded.to_string();
+                    if let Err(e) = handle.emit(channel, changes) {
+                        error!("Error al emitir evento: {e}");
+                    }
+                }
+            }
+            Err(e) => {
+                error!("[ERROR] No se pudo recargar {}: {:?}", path.display(), e);
+                // Se mantiene la configuración en memoria pero se bloquea
+                // el guardado para no pisar el archivo editado a mano
+                enter_recovery(&e, None);
+            }
+        }
+    }
+
     pub fn shutdown(&self) {
         let _ = self.shutdown_tx.send(());
     }
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Deserialize, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BackupInfo {
+    pub file_name: String,
+    /// Milisegundos desde UNIX_EPOCH.
+    pub timestamp: u64,
+    pub size: u64,
+}
@@ -1,12 +1,84 @@
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-use crate::domain::profiles::Profile;
+use crate::domain::{enums::KeyboardLayout, macros::Macro, profiles::Profile};
 
-#[derive(Debug, Deserialize, Serialize, Clone, TS, Default)]
+/// Versión actual del formato en disco. La 1 garantiza IDs sin colisiones.
+pub const CONFIG_VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize, Serialize, Clone, TS, Default, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct Config {
     pub profiles: Vec<Profile>,
     pub selected_profile_id: Option<u64>,
+    /// Macros reutilizables que no pertenecen a ningún perfil. No se disparan
+    /// con teclas; solo se ejecutan desde un paso `CallMacro`.
+    #[serde(default)]
+    pub library: Vec<Macro>,
+    #[serde(default)]
+    pub keyboard_layout: KeyboardLayout,
+    #[serde(default)]
+    #[ts(skip)]
+    pub version: u32,
+    /// Siguiente ID que asignará el backend.
+    #[serde(default)]
+    #[ts(skip)]
+    pub next_id: u64,
+}
+
+/// Estado de recuperación cuando `config.json` no se pudo leer.
+///
+/// Mientras exista, el guardado queda bloqueado para no sobrescribir el
+/// archivo original del usuario.
+#[derive(Debug, Deserialize, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ConfigRecovery {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Adonde se apartó el archivo dañado, con marca de tiempo, si se pudo renombrar.
+    pub backup_path: Option<String>,
+}
+
+/// Cambios detectados al recargar la configuración desde disco.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ConfigDiff {
+    pub added_profiles: Vec<u64>,
+    pub removed_profiles: Vec<u64>,
+    pub changed_profiles: Vec<u64>,
+    pub profiles_reordered: bool,
+    pub added_macros: Vec<u64>,
+    pub removed_macros: Vec<u64>,
+    pub changed_macros: Vec<u64>,
+    pub library_reordered: bool,
+    pub keyboard_layout_changed: bool,
+    pub selected_profile_changed: bool,
+}
+
+impl ConfigDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `true` si lo único que cambió fue el perfil activo (o nada).
+    pub fn is_selection_only(&self) -> bool {
+        Self {
+            selected_profile_changed: false,
+            ..self.clone()
+        }
+        .is_empty()
+    }
+}
+
+/// Resultado de una mutación junto con la revisión resultante de la configuración.
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Revisioned<T> {
+    pub revision: u64,
+    pub data: T,
 }
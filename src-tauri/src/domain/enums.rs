@@ -12,6 +12,19 @@ pub enum ModifierKey {
     Meta,
 }
 
+/// Distribución del teclado del usuario, para escribir texto con `TypeText`.
+#[derive(
+    Debug, Default, Deserialize, Serialize, Clone, Copy, TS, EnumString, Display, PartialEq, Eq,
+)]
+#[ts(export)]
+pub enum KeyboardLayout {
+    /// Inglés (EE. UU.)
+    #[default]
+    Us,
+    /// Español (España)
+    Es,
+}
+
 #[derive(
     Debug,
     Clone,
@@ -33,4 +46,10 @@ pub enum ListenableChannel {
     #[serde(rename = "sequence-step")]
     #[strum(serialize = "sequence-step")]
     SequenceStep,
+    #[serde(rename = "config-recovery")]
+    #[strum(serialize = "config-recovery")]
+    ConfigRecovery,
+    #[serde(rename = "config-reloaded")]
+    #[strum(serialize = "config-reloaded")]
+    ConfigReloaded,
 }
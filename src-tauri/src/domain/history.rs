@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Deserialize, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct HistoryEntry {
+    pub description: String,
+    /// Milisegundos desde UNIX_EPOCH.
+    pub timestamp: u64,
+}
+
+/// Registro de ediciones para la UI; el último elemento es el más reciente.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, Default)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct HistoryLog {
+    pub undo: Vec<HistoryEntry>,
+    pub redo: Vec<HistoryEntry>,
+}
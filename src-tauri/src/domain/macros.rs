@@ -10,11 +10,15 @@ pub struct KeyCombination {
     pub key: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, TS)]
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq, Eq)]
 #[ts(export)]
 pub struct Macro {
     pub id: u64,
     pub name: String,
     pub trigger: KeyCombination,
     pub sequence: Vec<SequenceStep>,
+    /// Script Rhai que sustituye a `sequence` al ejecutar la macro.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub script: Option<String>,
 }
@@ -1,5 +1,12 @@
+pub mod backup;
 pub mod config;
 pub mod enums;
+pub mod history;
 pub mod macros;
+pub mod preview;
 pub mod profiles;
+pub mod run;
 pub mod sequence_step;
+pub mod share;
+pub mod validation;
+pub mod variables;
@@ -0,0 +1,72 @@
+use rdev::{Button, EventType};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Evento que la macro habría enviado al sistema.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[ts(export)]
+pub enum OutputEvent {
+    KeyDown { key: String },
+    KeyUp { key: String },
+    ButtonDown { button: String },
+    ButtonUp { button: String },
+    MouseMove { x: f64, y: f64 },
+    Scroll { dx: i64, dy: i64 },
+}
+
+/// Mismos nombres que acepta la configuración; los botones extra empiezan en MOUSE4.
+fn button_name(button: Button) -> String {
+    match button {
+        Button::Left => "MOUSE1".to_string(),
+        Button::Right => "MOUSE2".to_string(),
+        Button::Middle => "MOUSE3".to_string(),
+        Button::Unknown(n) => format!("MOUSE{}", u16::from(n) + 3),
+    }
+}
+
+impl From<&EventType> for OutputEvent {
+    fn from(ev: &EventType) -> Self {
+        match *ev {
+            EventType::KeyPress(k) => OutputEvent::KeyDown {
+                key: format!("{k:?}"),
+            },
+            EventType::KeyRelease(k) => OutputEvent::KeyUp {
+                key: format!("{k:?}"),
+            },
+            EventType::ButtonPress(b) => OutputEvent::ButtonDown {
+                button: button_name(b),
+            },
+            EventType::ButtonRelease(b) => OutputEvent::ButtonUp {
+                button: button_name(b),
+            },
+            EventType::MouseMove { x, y } => OutputEvent::MouseMove { x, y },
+            EventType::Wheel { delta_x, delta_y } => OutputEvent::Scroll {
+                dx: delta_x,
+                dy: delta_y,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct TimelineEntry {
+    /// Microsegundos desde el inicio, en tiempo virtual.
+    pub at_us: u64,
+    pub event: OutputEvent,
+}
+
+/// Resultado de ejecutar una macro sin enviar nada al sistema.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Preview {
+    pub timeline: Vec<TimelineEntry>,
+    pub duration_us: u64,
+    /// Error con el que se habría detenido la macro, si lo hubo.
+    pub error: Option<String>,
+    /// La macro pasó los límites de la vista previa y no se muestra entera.
+    pub truncated: bool,
+}
@@ -3,7 +3,7 @@ use ts_rs::TS;
 
 use crate::domain::macros::Macro;
 
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq, Eq)]
 #[ts(export)]
 #[serde(rename_all = "camelCase")]
 pub struct Profile {
@@ -11,4 +11,7 @@ pub struct Profile {
     pub name: String,
     pub function_key: Option<String>,
     pub macros: Vec<Macro>,
+    /// Conserva las variables de ejecución del perfil entre reinicios.
+    #[serde(default)]
+    pub persist_variables: bool,
 }
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::domain::preview::OutputEvent;
+
+/// Cómo terminó una ejecución.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, TS, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum RunOutcome {
+    Completed,
+    /// Detenida por el apagado o por la propia macro (`WaitForKey` con `abort`).
+    Cancelled,
+    Failed,
+}
+
+/// Estado de una ejecución pedida desde la UI o por un disparador.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "lowercase")]
+#[ts(export)]
+pub enum RunStatus {
+    /// En la cola o esperando su `delay_before_ms`.
+    Queued,
+    Running,
+    Finished {
+        outcome: RunOutcome,
+        error: Option<String>,
+    },
+}
+
+impl RunStatus {
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        matches!(self, RunStatus::Finished { .. })
+    }
+}
+
+/// Progreso de una ejecución; llega a la UI dentro de un [`RunEventBatch`].
+/// Las marcas de tiempo son microsegundos desde la época Unix.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+#[ts(export)]
+pub enum RunEvent {
+    RunStarted {
+        run_id: u64,
+        macro_id: Option<u64>,
+        at_us: u64,
+    },
+    /// `index` es la posición del paso en su lista y `depth` cuántos pasos
+    /// compuestos o llamadas lo envuelven.
+    StepStarted {
+        run_id: u64,
+        step_id: u64,
+        index: usize,
+        depth: usize,
+        at_us: u64,
+    },
+    StepFinished {
+        run_id: u64,
+        step_id: u64,
+        index: usize,
+        depth: usize,
+        at_us: u64,
+    },
+    RunFinished {
+        run_id: u64,
+        outcome: RunOutcome,
+        error: Option<String>,
+        at_us: u64,
+    },
+    /// Evento que una simulación habría enviado al sistema.
+    Output { event: OutputEvent, at_us: u64 },
+}
+
+/// Carga del canal `sequence-step`: los eventos de un intervalo de emisión, en
+/// el orden en que ocurrieron.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq)]
+#[ts(export)]
+pub struct RunEventBatch(pub Vec<RunEvent>);
+
+/// Tiempos de un paso, en microsegundos desde el inicio de la ejecución.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct StepTiming {
+    pub step_id: u64,
+    pub depth: usize,
+    /// Cuándo debía empezar según las esperas previstas.
+    pub intended_us: u64,
+    pub actual_us: u64,
+    pub duration_us: u64,
+}
+
+impl StepTiming {
+    /// Desviación entre el inicio real y el previsto.
+    #[inline]
+    pub fn jitter_us(&self) -> u64 {
+        self.actual_us.abs_diff(self.intended_us)
+    }
+}
+
+/// Informe de tiempos de una ejecución terminada.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RunReport {
+    pub run_id: u64,
+    pub macro_id: Option<u64>,
+    /// Microsegundos desde la época Unix.
+    pub started_at_us: u64,
+    pub duration_us: u64,
+    pub outcome: RunOutcome,
+    /// Desde la pulsación que disparó la macro hasta la primera tecla enviada.
+    pub trigger_latency_us: Option<u64>,
+    pub max_jitter_us: u64,
+    pub steps: Vec<StepTiming>,
+}
+
+/// Percentiles en microsegundos.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, TS, PartialEq, Eq, Default)]
+#[ts(export)]
+pub struct Percentiles {
+    pub p50: u64,
+    pub p95: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+/// Estadísticas sobre las ejecuciones del historial.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct LatencyStats {
+    pub runs: usize,
+    /// Solo ejecuciones disparadas por teclado que llegaron a enviar algo.
+    pub trigger_latency: Option<Percentiles>,
+    /// Desviación de cada paso respecto a su inicio previsto.
+    pub step_jitter: Option<Percentiles>,
+}
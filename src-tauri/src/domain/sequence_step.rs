@@ -1,8 +1,143 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumString};
 use ts_rs::TS;
 
-#[derive(Debug, Deserialize, Serialize, Clone, TS, EnumString, Display)]
+use crate::domain::variables::{Compare, VarValue};
+
+/// Fotogramas por segundo con los que se convierten las duraciones en fotogramas.
+pub const FRAMES_PER_SECOND: u32 = 60;
+
+/// Duración en milisegundos, fija o aleatoria para que la ejecución no sea
+/// siempre idéntica. Un número suelto es una duración fija.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, TS, PartialEq, Eq)]
+#[serde(untagged)]
+#[ts(export)]
+pub enum Timing {
+    Fixed(u64),
+    /// Normal, recortada a `[min, max]` si se indican.
+    Normal {
+        mean: u64,
+        stddev: u64,
+        #[serde(default)]
+        min: Option<u64>,
+        #[serde(default)]
+        max: Option<u64>,
+    },
+    /// Uniforme en `[min, max]`, ambos incluidos.
+    Uniform {
+        min: u64,
+        max: u64,
+    },
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing::Fixed(0)
+    }
+}
+
+/// Estado que espera `WaitForKey`.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, TS, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum WaitState {
+    #[default]
+    Pressed,
+    Released,
+}
+
+/// Qué hacer si `WaitForKey` agota el tiempo.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, TS, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum OnTimeout {
+    /// Detiene la macro sin ejecutar el resto de pasos.
+    #[default]
+    Abort,
+    Continue,
+}
+
+/// Lado hacia el que mira el personaje.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, TS, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum Side {
+    Left,
+    #[default]
+    Right,
+}
+
+/// Condición de un paso `If`. Se evalúa al llegar al paso, sin esperar.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq, Eq)]
+#[serde(rename_all = "lowercase", tag = "type")]
+#[ts(export)]
+pub enum Condition {
+    /// La tecla o botón está pulsado. "SHIFT", "CTRL" y "GUI" valen por cualquiera de los dos lados.
+    Held {
+        key: String,
+    },
+    /// El personaje mira hacia `side`, según el último `SetFacing`.
+    Facing {
+        side: Side,
+    },
+    /// Compara una variable del perfil con `value`.
+    Var {
+        name: String,
+        #[serde(default)]
+        op: Compare,
+        value: VarValue,
+    },
+    Not {
+        condition: Box<Condition>,
+    },
+    /// Se cumplen todas; sin condiciones es cierta.
+    All {
+        conditions: Vec<Condition>,
+    },
+    /// Se cumple alguna; sin condiciones es falsa.
+    Any {
+        conditions: Vec<Condition>,
+    },
+}
+
+impl Default for Condition {
+    fn default() -> Self {
+        Condition::All {
+            conditions: Vec::new(),
+        }
+    }
+}
+
+impl Condition {
+    /// Niveles de anidamiento; una condición simple cuenta 1.
+    pub fn depth(&self) -> usize {
+        match self {
+            Condition::Held { .. } | Condition::Facing { .. } | Condition::Var { .. } => 1,
+            Condition::Not { condition } => 1 + condition.depth(),
+            Condition::All { conditions } | Condition::Any { conditions } => {
+                1 + conditions.iter().map(Condition::depth).max().unwrap_or(0)
+            }
+        }
+    }
+
+    /// Recorre la condición y todas las que contiene.
+    pub fn visit<'a>(&'a self, f: &mut impl FnMut(&'a Condition)) {
+        f(self);
+        match self {
+            Condition::Held { .. } | Condition::Facing { .. } | Condition::Var { .. } => {}
+            Condition::Not { condition } => condition.visit(f),
+            Condition::All { conditions } | Condition::Any { conditions } => {
+                for c in conditions {
+                    c.visit(f);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, TS, EnumString, Display, PartialEq, Eq)]
 #[serde(rename_all = "lowercase", tag = "type")]
 #[ts(export)]
 pub enum SequenceStep {
@@ -11,5 +146,282 @@ pub enum SequenceStep {
     #[strum(to_string = "KeyUp")]
     KeyUp { id: u64, key: String },
     #[strum(to_string = "Delay")]
-    Delay { id: u64, ms: u64 },
+    Delay { id: u64, ms: Timing },
+    /// Mueve el cursor a `(x, y)` o, si `relative`, lo desplaza esa cantidad.
+    #[strum(to_string = "MouseMove")]
+    MouseMove {
+        id: u64,
+        x: i32,
+        y: i32,
+        #[serde(default)]
+        relative: bool,
+    },
+    #[strum(to_string = "MouseDown")]
+    MouseDown { id: u64, button: String },
+    #[strum(to_string = "MouseUp")]
+    MouseUp { id: u64, button: String },
+    /// Pulsa y suelta un botón del ratón.
+    #[strum(to_string = "MouseClick")]
+    MouseClick { id: u64, button: String },
+    /// Desplaza la rueda en muescas; positivo es arriba o a la derecha.
+    #[strum(to_string = "Scroll")]
+    Scroll {
+        id: u64,
+        #[serde(default)]
+        dx: i64,
+        dy: i64,
+    },
+    /// Espera a que la tecla o botón llegue a `state`; si ya lo está, sigue sin esperar.
+    #[strum(to_string = "WaitForKey")]
+    WaitForKey {
+        id: u64,
+        key: String,
+        state: WaitState,
+        #[serde(rename = "timeoutMs")]
+        timeout_ms: u64,
+        #[serde(rename = "onTimeout")]
+        on_timeout: OnTimeout,
+    },
+    /// Ejecuta los pasos de otra macro, normalmente una de la biblioteca.
+    #[strum(to_string = "CallMacro")]
+    CallMacro {
+        id: u64,
+        #[serde(rename = "macroId")]
+        macro_id: u64,
+    },
+    /// Pulsa y suelta una tecla. `hold_frames`, si se indica, sustituye a `hold_ms`.
+    #[strum(to_string = "Tap")]
+    Tap {
+        id: u64,
+        key: String,
+        #[serde(rename = "holdMs")]
+        hold_ms: Timing,
+        #[serde(default, rename = "holdFrames")]
+        hold_frames: Option<u32>,
+    },
+    /// Pulsa todas las teclas a la vez y las suelta juntas en orden inverso.
+    #[strum(to_string = "Chord")]
+    Chord {
+        id: u64,
+        keys: Vec<String>,
+        #[serde(rename = "holdMs")]
+        hold_ms: Timing,
+        #[serde(default, rename = "holdFrames")]
+        hold_frames: Option<u32>,
+    },
+    /// Escribe el texto tecla a tecla según la distribución configurada.
+    #[strum(to_string = "TypeText")]
+    TypeText {
+        id: u64,
+        text: String,
+        #[serde(rename = "perCharDelayMs")]
+        per_char_delay_ms: u64,
+    },
+    /// Ejecuta `body` `count` veces seguidas.
+    #[strum(to_string = "Repeat")]
+    Repeat {
+        id: u64,
+        count: u32,
+        body: Vec<SequenceStep>,
+    },
+    /// Ejecuta `body` mientras siga pulsada la tecla que disparó la macro.
+    #[strum(to_string = "LoopUntilReleased")]
+    LoopUntilReleased { id: u64, body: Vec<SequenceStep> },
+    /// Ejecuta `then` si se cumple la condición y `else` si no.
+    #[strum(to_string = "If")]
+    If {
+        id: u64,
+        condition: Condition,
+        then: Vec<SequenceStep>,
+        #[serde(default, rename = "else")]
+        else_: Vec<SequenceStep>,
+    },
+    /// Indica hacia dónde mira el personaje, para las condiciones `Facing`.
+    #[strum(to_string = "SetFacing")]
+    SetFacing { id: u64, side: Side },
+    /// Da valor a una variable del perfil.
+    #[strum(to_string = "SetVar")]
+    SetVar {
+        id: u64,
+        name: String,
+        value: VarValue,
+    },
+    /// Suma `by` a una variable entera del perfil; con `wrap` vuelve a 0 al
+    /// llegar a ese valor, p. ej. para alternar entre varios remates.
+    #[strum(to_string = "IncrementVar")]
+    IncrementVar {
+        id: u64,
+        name: String,
+        by: i64,
+        #[serde(default)]
+        wrap: Option<i64>,
+    },
+    /// Script Rhai con acceso a `press`, `release`, `tap`, `wait`,
+    /// `is_pressed`, `facing` y las variables de ejecución.
+    #[strum(to_string = "Script")]
+    Script { id: u64, source: String },
+}
+
+impl SequenceStep {
+    #[inline]
+    pub fn id(&self) -> u64 {
+        match self {
+            SequenceStep::KeyDown { id, .. }
+            | SequenceStep::KeyUp { id, .. }
+            | SequenceStep::Delay { id, .. }
+            | SequenceStep::Tap { id, .. }
+            | SequenceStep::Chord { id, .. }
+            | SequenceStep::TypeText { id, .. }
+            | SequenceStep::MouseMove { id, .. }
+            | SequenceStep::MouseDown { id, .. }
+            | SequenceStep::MouseUp { id, .. }
+            | SequenceStep::MouseClick { id, .. }
+            | SequenceStep::Scroll { id, .. }
+            | SequenceStep::WaitForKey { id, .. }
+            | SequenceStep::CallMacro { id, .. }
+            | SequenceStep::Repeat { id, .. }
+            | SequenceStep::LoopUntilReleased { id, .. }
+            | SequenceStep::If { id, .. }
+            | SequenceStep::SetFacing { id, .. }
+            | SequenceStep::SetVar { id, .. }
+            | SequenceStep::IncrementVar { id, .. }
+            | SequenceStep::Script { id, .. } => *id,
+        }
+    }
+
+    #[inline]
+    pub fn set_id(&mut self, new_id: u64) {
+        match self {
+            SequenceStep::KeyDown { id, .. }
+            | SequenceStep::KeyUp { id, .. }
+            | SequenceStep::Delay { id, .. }
+            | SequenceStep::Tap { id, .. }
+            | SequenceStep::Chord { id, .. }
+            | SequenceStep::TypeText { id, .. }
+            | SequenceStep::MouseMove { id, .. }
+            | SequenceStep::MouseDown { id, .. }
+            | SequenceStep::MouseUp { id, .. }
+            | SequenceStep::MouseClick { id, .. }
+            | SequenceStep::Scroll { id, .. }
+            | SequenceStep::WaitForKey { id, .. }
+            | SequenceStep::CallMacro { id, .. }
+            | SequenceStep::Repeat { id, .. }
+            | SequenceStep::LoopUntilReleased { id, .. }
+            | SequenceStep::If { id, .. }
+            | SequenceStep::SetFacing { id, .. }
+            | SequenceStep::SetVar { id, .. }
+            | SequenceStep::IncrementVar { id, .. }
+            | SequenceStep::Script { id, .. } => *id = new_id,
+        }
+    }
+
+    /// Cuerpos anidados de los pasos compuestos, siempre en el mismo orden
+    /// para cada variante (en `If`, `then` y luego `else`). Vacío en los pasos simples.
+    #[inline]
+    pub fn bodies(&self) -> Vec<&Vec<SequenceStep>> {
+        match self {
+            SequenceStep::Repeat { body, .. } | SequenceStep::LoopUntilReleased { body, .. } => {
+                vec![body]
+            }
+            SequenceStep::If { then, else_, .. } => vec![then, else_],
+            _ => Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn bodies_mut(&mut self) -> Vec<&mut Vec<SequenceStep>> {
+        match self {
+            SequenceStep::Repeat { body, .. } | SequenceStep::LoopUntilReleased { body, .. } => {
+                vec![body]
+            }
+            SequenceStep::If { then, else_, .. } => vec![then, else_],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Duración de una pulsación; los fotogramas tienen prioridad sobre los
+/// milisegundos, que llegan ya muestreados de su [`Timing`].
+#[inline]
+pub fn hold_duration(hold_ms: u64, hold_frames: Option<u32>) -> Duration {
+    match hold_frames {
+        Some(frames) => Duration::from_secs_f64(f64::from(frames) / f64::from(FRAMES_PER_SECOND)),
+        None => Duration::from_millis(hold_ms),
+    }
+}
+
+/// Niveles de pasos compuestos anidados; una secuencia sin compuestos cuenta 1.
+pub fn nesting_depth(steps: &[SequenceStep]) -> usize {
+    steps
+        .iter()
+        .flat_map(SequenceStep::bodies)
+        .map(|body| 1 + nesting_depth(body))
+        .max()
+        .unwrap_or(1)
+}
+
+/// Recorre los pasos en profundidad, entrando en los cuerpos de los compuestos.
+pub fn visit_steps<'a>(steps: &'a [SequenceStep], f: &mut impl FnMut(&'a SequenceStep)) {
+    for step in steps {
+        f(step);
+        for body in step.bodies() {
+            visit_steps(body, f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facing() -> Condition {
+        Condition::Facing { side: Side::Left }
+    }
+
+    #[test]
+    fn condition_depth_counts_the_deepest_branch() {
+        let not = |c| Condition::Not {
+            condition: Box::new(c),
+        };
+        let cases = [
+            (facing(), 1),
+            (Condition::default(), 1),
+            (not(facing()), 2),
+            (not(not(facing())), 3),
+            (
+                Condition::All {
+                    conditions: vec![facing(), not(not(facing()))],
+                },
+                4,
+            ),
+            (
+                Condition::Any {
+                    conditions: vec![Condition::All {
+                        conditions: vec![facing()],
+                    }],
+                },
+                3,
+            ),
+        ];
+
+        for (condition, expected) in cases {
+            assert_eq!(condition.depth(), expected, "{condition:?}");
+        }
+    }
+
+    #[test]
+    fn visit_reaches_nested_conditions() {
+        let condition = Condition::Any {
+            conditions: vec![
+                facing(),
+                Condition::Not {
+                    condition: Box::new(facing()),
+                },
+            ],
+        };
+
+        let mut count = 0;
+        condition.visit(&mut |_| count += 1);
+        assert_eq!(count, 4);
+    }
 }
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::domain::{
+    macros::{KeyCombination, Macro},
+    profiles::Profile,
+};
+
+/// Identifica los archivos de intercambio para rechazar JSON ajeno.
+pub const SHARED_FORMAT: &str = "fight-macros/share";
+/// La 2 añade `library`; las versiones anteriores la ignorarían.
+pub const SHARED_VERSION: u32 = 2;
+
+/// Archivo autocontenido para compartir un perfil o una macro.
+#[derive(Debug, Deserialize, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SharedFile {
+    pub format: String,
+    pub version: u32,
+    pub content: SharedContent,
+    /// Macros que llaman los pasos de `content` con `CallMacro`, directa o
+    /// indirectamente. Al importar se crean en la biblioteca.
+    #[serde(default)]
+    pub library: Vec<Macro>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, TS)]
+#[serde(rename_all = "lowercase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum SharedContent {
+    Profile(Profile),
+    Macro(Macro),
+}
+
+/// Macro importada cuyo disparador ya usa otra macro del perfil destino.
+#[derive(Debug, Deserialize, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct TriggerConflict {
+    pub imported_macro_id: u64,
+    pub existing_macro_id: u64,
+    pub trigger: KeyCombination,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ImportReport {
+    pub profile_id: u64,
+    /// IDs nuevos asignados a las macros importadas.
+    pub macro_ids: Vec<u64>,
+    /// IDs nuevos de las macros llamadas, creadas en la biblioteca.
+    pub library_macro_ids: Vec<u64>,
+    pub conflicts: Vec<TriggerConflict>,
+    /// La tecla de función del perfil importado ya la usaba otro perfil y se descartó.
+    pub function_key_dropped: bool,
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Problema en la configuración que impediría ejecutar una macro.
+#[derive(Debug, Deserialize, Serialize, Clone, TS, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ValidationIssue {
+    pub macro_id: u64,
+    /// Paso concreto que causa el problema, si lo hay.
+    pub step_id: Option<u64>,
+    pub message: String,
+}
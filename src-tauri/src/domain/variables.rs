@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Valor de una variable de ejecución.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, TS, PartialEq, Eq)]
+#[serde(untagged)]
+#[ts(export)]
+pub enum VarValue {
+    Bool(bool),
+    Int(i64),
+}
+
+impl Default for VarValue {
+    fn default() -> Self {
+        VarValue::Int(0)
+    }
+}
+
+/// Comparación de una condición `Var`.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, TS, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum Compare {
+    #[default]
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Compare {
+    /// Una variable sin valor vale 0 o `false` según el tipo de `expected`;
+    /// con tipos distintos solo se cumple `Ne`.
+    pub fn test(self, actual: Option<VarValue>, expected: VarValue) -> bool {
+        let actual = actual.unwrap_or(match expected {
+            VarValue::Bool(_) => VarValue::Bool(false),
+            VarValue::Int(_) => VarValue::Int(0),
+        });
+        let ordering = match (actual, expected) {
+            (VarValue::Int(a), VarValue::Int(b)) => a.cmp(&b),
+            (VarValue::Bool(a), VarValue::Bool(b)) => a.cmp(&b),
+            _ => return self == Compare::Ne,
+        };
+
+        match self {
+            Compare::Eq => ordering.is_eq(),
+            Compare::Ne => ordering.is_ne(),
+            Compare::Lt => ordering.is_lt(),
+            Compare::Le => ordering.is_le(),
+            Compare::Gt => ordering.is_gt(),
+            Compare::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_integers() {
+        use Compare::*;
+        let cases = [
+            (Eq, [false, true, false]),
+            (Ne, [true, false, true]),
+            (Lt, [true, false, false]),
+            (Le, [true, true, false]),
+            (Gt, [false, false, true]),
+            (Ge, [false, true, true]),
+        ];
+
+        for (op, expected) in cases {
+            let got =
+                [1, 2, 3].map(|actual| op.test(Some(VarValue::Int(actual)), VarValue::Int(2)));
+            assert_eq!(got, expected, "{op:?}");
+        }
+    }
+
+    #[test]
+    fn compares_booleans() {
+        let t = Some(VarValue::Bool(true));
+        assert!(Compare::Eq.test(t, VarValue::Bool(true)));
+        assert!(Compare::Ne.test(t, VarValue::Bool(false)));
+        assert!(Compare::Gt.test(t, VarValue::Bool(false)));
+        assert!(!Compare::Lt.test(t, VarValue::Bool(false)));
+    }
+
+    #[test]
+    fn missing_variable_is_zero_or_false() {
+        assert!(Compare::Eq.test(None, VarValue::Int(0)));
+        assert!(Compare::Lt.test(None, VarValue::Int(1)));
+        assert!(Compare::Gt.test(None, VarValue::Int(-1)));
+        assert!(Compare::Eq.test(None, VarValue::Bool(false)));
+        assert!(!Compare::Eq.test(None, VarValue::Bool(true)));
+    }
+
+    #[test]
+    fn mismatched_types_only_satisfy_ne() {
+        use Compare::*;
+        for op in [Eq, Lt, Le, Gt, Ge] {
+            assert!(
+                !op.test(Some(VarValue::Int(1)), VarValue::Bool(true)),
+                "{op:?}"
+            );
+            assert!(
+                !op.test(Some(VarValue::Bool(false)), VarValue::Int(0)),
+                "{op:?}"
+            );
+        }
+        assert!(Ne.test(Some(VarValue::Int(1)), VarValue::Bool(true)));
+    }
+}
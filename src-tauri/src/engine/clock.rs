@@ -0,0 +1,105 @@
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crossbeam_channel::{bounded, Receiver, Sender};
+use parking_lot::Mutex;
+use tracing::info;
+
+use crate::engine::run_events::to_us;
+
+/// Fuente de tiempo del ejecutor y del informe de tiempos. Con un
+/// [`ManualClock`] las esperas solo avanzan cuando se adelanta el reloj.
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Microsegundos desde la época Unix.
+    fn now_us(&self) -> u64;
+
+    /// Canal que recibe un mensaje cuando pasa `duration`, como
+    /// [`crossbeam_channel::after`]; el `Instant` recibido no significa nada.
+    fn after(&self, duration: Duration) -> Receiver<Instant>;
+
+    /// Espera `duration`; devuelve `false` si `cancel_rx` la cortó antes.
+    fn sleep(&self, duration: Duration, cancel_rx: &Receiver<()>) -> bool {
+        let timer = self.after(duration);
+        crossbeam::select! {
+            recv(timer) -> _ => true,
+            recv(cancel_rx) -> _ => {
+                info!("Delay interrupted by cancellation");
+                false
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now_us(&self) -> u64 {
+        to_us(SystemTime::now())
+    }
+
+    #[inline]
+    fn after(&self, duration: Duration) -> Receiver<Instant> {
+        crossbeam_channel::after(duration)
+    }
+}
+
+/// Reloj de la aplicación.
+#[inline]
+pub fn system() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+#[derive(Debug, Default)]
+struct ManualState {
+    now_us: u64,
+    /// Temporizadores pendientes y cuándo vencen.
+    timers: Vec<(u64, Sender<Instant>)>,
+}
+
+/// Reloj que empieza en 0 y solo avanza con [`ManualClock::advance`]; sirve
+/// para repetir ejecuciones en tiempo virtual sin esperar de verdad.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    state: Mutex<ManualState>,
+}
+
+impl ManualClock {
+    /// Adelanta el reloj y dispara los temporizadores que vencen.
+    pub fn advance(&self, by: Duration) {
+        let mut state = self.state.lock();
+        state.now_us = state.now_us.saturating_add(by.as_micros() as u64);
+        let now_us = state.now_us;
+        state.timers.retain(|(due_us, tx)| {
+            if *due_us > now_us {
+                return true;
+            }
+            let _ = tx.try_send(Instant::now());
+            false
+        });
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline]
+    fn now_us(&self) -> u64 {
+        self.state.lock().now_us
+    }
+
+    fn after(&self, duration: Duration) -> Receiver<Instant> {
+        let (tx, rx) = bounded(1);
+        if duration.is_zero() {
+            let _ = tx.try_send(Instant::now());
+            return rx;
+        }
+
+        let mut state = self.state.lock();
+        let due_us = state.now_us.saturating_add(duration.as_micros() as u64);
+        state.timers.push((due_us, tx));
+        rx
+    }
+}
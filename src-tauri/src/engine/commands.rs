@@ -0,0 +1,107 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
+use tauri::State;
+
+use crate::{
+    config::{handler::CONFIG, validation::MAX_STEP_DEPTH},
+    domain::{
+        preview::Preview,
+        run::{LatencyStats, RunReport, RunStatus},
+        sequence_step::{nesting_depth, SequenceStep},
+    },
+    engine::{
+        executor::Executor, handler::QueueHandler, preview, queue::RunJob, recorder,
+        runtime::Variables,
+    },
+};
+
+#[tauri::command]
+pub fn get_variables(executor: State<'_, Arc<Executor>>, profile_id: u64) -> Variables {
+    executor.runtime().variables(Some(profile_id))
+}
+
+/// Sin `profile_id` se borran las variables de todos los perfiles.
+#[tauri::command]
+pub fn reset_variables(
+    executor: State<'_, Arc<Executor>>,
+    profile_id: Option<u64>,
+) -> Result<(), String> {
+    let runtime = executor.runtime();
+    runtime.reset_variables(profile_id);
+    runtime.flush().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_run_history() -> Vec<RunReport> {
+    recorder::history()
+}
+
+#[tauri::command]
+pub fn get_latency_stats() -> LatencyStats {
+    recorder::stats()
+}
+
+#[tauri::command]
+pub fn export_run_history(path: String) -> Result<(), String> {
+    recorder::export_csv(Path::new(&path)).map_err(|e| format!("{e:#}"))
+}
+
+#[tauri::command]
+pub fn preview_macro(executor: State<'_, Arc<Executor>>, id: u64) -> Result<Preview, String> {
+    preview::preview_macro(&executor, id).map_err(|e| format!("{e:#}"))
+}
+
+#[tauri::command]
+pub fn simulate_macro(executor: State<'_, Arc<Executor>>, id: u64) -> Result<u64, String> {
+    preview::simulate_macro(&executor, id).map_err(|e| format!("{e:#}"))
+}
+
+/// Encola la macro como si se hubiera disparado y devuelve el id de la ejecución.
+#[tauri::command]
+pub fn run_macro(
+    queue: State<'_, QueueHandler>,
+    id: u64,
+    delay_before_ms: u64,
+) -> Result<u64, String> {
+    let macro_def = CONFIG
+        .read()
+        .find_macro(&id)
+        .cloned()
+        .ok_or_else(|| format!("Macro {id} no encontrada"))?;
+    queue
+        .push(
+            RunJob::Macro(macro_def),
+            Duration::from_millis(delay_before_ms),
+            None,
+        )
+        .map_err(|e| format!("{e:#}"))
+}
+
+/// Encola pasos sueltos con las variables del perfil activo.
+#[tauri::command]
+pub fn run_sequence(
+    queue: State<'_, QueueHandler>,
+    steps: Vec<SequenceStep>,
+) -> Result<u64, String> {
+    let nesting = nesting_depth(&steps);
+    if nesting > MAX_STEP_DEPTH {
+        return Err(format!(
+            "La secuencia anida {nesting} niveles de pasos y el máximo es {MAX_STEP_DEPTH}"
+        ));
+    }
+    queue
+        .push(RunJob::Sequence(steps), Duration::ZERO, None)
+        .map_err(|e| format!("{e:#}"))
+}
+
+/// `false` si la ejecución ya terminó o no existe.
+#[tauri::command]
+pub fn cancel_run(executor: State<'_, Arc<Executor>>, run_id: u64) -> bool {
+    executor.cancel_run(run_id)
+}
+
+/// `None` si la ejecución no existe o se olvidó por antigua.
+#[tauri::command]
+pub fn get_run_status(executor: State<'_, Arc<Executor>>, run_id: u64) -> Option<RunStatus> {
+    executor.run_status(run_id)
+}
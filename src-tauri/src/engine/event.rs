@@ -1,8 +1,11 @@
 use anyhow::Result;
 use rdev::{simulate, EventType};
 
+use crate::engine::recorder;
+
 pub fn send_event(ev: EventType) -> Result<()> {
     simulate(&ev)?;
+    recorder::output();
 
     Ok(())
 }
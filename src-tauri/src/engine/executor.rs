@@ -1,107 +1,1202 @@
 use std::{
+    collections::VecDeque,
+    fmt,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
-    thread::sleep,
     time::Duration,
 };
 
-use anyhow::Result;
-use crossbeam_channel::{bounded, Receiver, Sender};
+use ahash::AHashMap;
+use anyhow::{bail, Context, Result};
+use crossbeam_channel::{bounded, never, Receiver, Sender};
 use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rdev::EventType;
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::{domain::sequence_step::SequenceStep, engine::event::send_event, keys};
+use crate::{
+    config::{handler::CONFIG, validation::MAX_CALL_DEPTH},
+    domain::run::{RunEvent, RunOutcome, RunStatus},
+    domain::sequence_step::{hold_duration, Condition, OnTimeout, SequenceStep, Timing, WaitState},
+    engine::{
+        clock::{self, Clock},
+        output::{OutputBackend, OutputKind, SystemOutput},
+        recorder, run_events,
+        runtime::RuntimeState,
+        script::{self, ScriptHost},
+    },
+    input::{
+        global::subscribe_input,
+        state::{transition, KEY_STATE},
+    },
+    keys::{self, InputKey},
+};
+
+/// De dónde viene una ejecución.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunSource {
+    /// Macro que se ejecuta, si la secuencia pertenece a una.
+    pub macro_id: Option<u64>,
+    /// Perfil cuyas variables usan los pasos.
+    pub profile_id: Option<u64>,
+    /// Tecla o botón que disparó la macro, si lo hubo.
+    pub trigger: Option<InputKey>,
+    /// Cuándo se pulsó el disparador, en microsegundos desde la época Unix.
+    pub triggered_at_us: Option<u64>,
+    /// Id reservado con [`Executor::register_run`]; sin él se reserva uno al empezar.
+    pub run_id: Option<u64>,
+    /// Espera antes del primer paso; cancelar la ejecución también la corta.
+    pub delay_before: Duration,
+}
+
+/// Datos de la ejecución en curso que heredan los pasos anidados.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunContext {
+    /// Identifica la ejecución en los eventos de progreso.
+    pub run_id: u64,
+    /// Tecla o botón que disparó la macro; `LoopUntilReleased` se repite mientras siga pulsado.
+    pub trigger: Option<InputKey>,
+    /// Llamadas `CallMacro` que envuelven al paso actual.
+    pub depth: usize,
+    /// Perfil cuyas variables usan los pasos; las macros llamadas heredan el del llamador.
+    pub profile_id: Option<u64>,
+    /// Pasos compuestos y llamadas que envuelven al paso actual.
+    pub nesting: usize,
+}
+
+/// Detiene la macro sin tratarlo como un fallo, p. ej. `WaitForKey` con
+/// `on_timeout: abort`.
+#[derive(Debug)]
+pub struct Aborted;
+
+impl fmt::Display for Aborted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Macro interrumpida")
+    }
+}
+
+impl std::error::Error for Aborted {}
+
+/// Fija la semilla de las duraciones aleatorias para repetir una ejecución.
+pub const SEED_ENV_VAR: &str = "FIGHT_MACROS_SEED";
+
+/// Ejecuciones terminadas cuyo estado se conserva para consultarlo.
+const MAX_FINISHED_RUNS: usize = 256;
+
+#[derive(Debug)]
+struct RunEntry {
+    status: RunStatus,
+    /// Soltarlo cancela la ejecución: `cancel_rx` se desconecta y corta sus esperas.
+    cancel_tx: Option<Sender<()>>,
+    cancel_rx: Receiver<()>,
+}
+
+#[derive(Debug, Default)]
+struct Runs {
+    entries: AHashMap<u64, RunEntry>,
+    /// Terminadas, de la más antigua a la más reciente.
+    finished: VecDeque<u64>,
+}
 
 #[derive(Debug)]
 pub struct Executor {
-    active: AtomicBool,
-    shutdown_tx: Sender<()>,
-    shutdown_rx: Receiver<()>,
+    active: Arc<AtomicBool>,
+    runs: Arc<Mutex<Runs>>,
     current_sequence: Mutex<Option<Arc<Vec<SequenceStep>>>>,
+    rng: Mutex<StdRng>,
+    /// Semilla inicial de `rng`, para que las copias aparte muestreen igual.
+    seed: u64,
+    runtime: Arc<RuntimeState>,
+    next_run_id: Arc<AtomicU64>,
+    output: Arc<dyn OutputBackend>,
+    clock: Arc<dyn Clock>,
 }
 
 impl Executor {
     pub fn new() -> Result<Self> {
-        let (shutdown_tx, shutdown_rx) = bounded(1);
-        Ok(Self {
-            active: AtomicBool::new(true),
-            shutdown_rx,
-            shutdown_tx,
+        let seed = std::env::var(SEED_ENV_VAR)
+            .ok()
+            .and_then(|s| s.trim().parse().ok());
+        let mut executor = Self::with_seed(seed);
+        executor.runtime = Arc::new(RuntimeState::load());
+        Ok(executor)
+    }
+
+    /// Sin semilla se elige una al azar; se registra para poder reproducir la ejecución.
+    pub fn with_seed(seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(rand::random);
+        info!("Semilla del ejecutor: {}", seed);
+
+        Self {
+            active: Arc::new(AtomicBool::new(true)),
+            runs: Arc::default(),
             current_sequence: Mutex::new(None),
-        })
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            seed,
+            runtime: Arc::default(),
+            next_run_id: Arc::new(AtomicU64::new(1)),
+            output: Arc::new(SystemOutput),
+            clock: clock::system(),
+        }
+    }
+
+    /// Cambia el reloj de las esperas y las marcas de tiempo, p. ej. por un
+    /// [`clock::ManualClock`] para repetir una ejecución en tiempo virtual.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Ejecutor aparte para la vista previa o la simulación: envía a `output`
+    /// y trabaja sobre una copia del estado de ejecución. Parte de la misma
+    /// semilla, así que dos vistas previas de una macro dan el mismo resultado.
+    ///
+    /// Comparte los ids y el registro de ejecuciones, así que `cancel_run` y
+    /// `shutdown` de este ejecutor también alcanzan las de la copia.
+    pub fn detached(&self, output: Arc<dyn OutputBackend>) -> Self {
+        Self {
+            output,
+            runtime: Arc::new(self.runtime.detached_copy()),
+            active: self.active.clone(),
+            runs: self.runs.clone(),
+            next_run_id: self.next_run_id.clone(),
+            ..Self::with_seed(Some(self.seed))
+        }
+    }
+
+    /// Muestrea una duración en milisegundos.
+    fn sample(&self, timing: &Timing) -> u64 {
+        match *timing {
+            Timing::Fixed(ms) => ms,
+            Timing::Uniform { min, max } => self.rng.lock().gen_range(min.min(max)..=max.max(min)),
+            Timing::Normal {
+                mean,
+                stddev,
+                min,
+                max,
+            } => {
+                // Box-Muller; `1 - u` evita ln(0)
+                let mut rng = self.rng.lock();
+                let u1 = 1.0 - rng.gen::<f64>();
+                let u2 = rng.gen::<f64>();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+
+                let ms = (mean as f64 + z * stddev as f64).round().max(0.0) as u64;
+                ms.max(min.unwrap_or(0)).min(max.unwrap_or(u64::MAX))
+            }
+        }
     }
     #[inline]
-    pub fn execute(&self, action: &SequenceStep) -> Result<()> {
+    pub fn execute(&self, action: &SequenceStep, ctx: RunContext) -> Result<()> {
         match action {
             SequenceStep::KeyDown { key, .. } => {
                 info!("Pulsando [{}]", key);
                 let k = keys::str_to_key(key);
-                send_event(EventType::KeyPress(k))?;
+                self.send(EventType::KeyPress(k))?;
             }
             SequenceStep::KeyUp { key, .. } => {
                 info!("Soltando [{}]", key);
                 let k = keys::str_to_key(key);
-                send_event(EventType::KeyRelease(k))?;
+                self.send(EventType::KeyRelease(k))?;
             }
             SequenceStep::Delay { ms, .. } => {
+                let ms = self.sample(ms);
                 info!("Pausando [{}] ms", ms);
+                self.wait(Duration::from_millis(ms), ctx)?;
+            }
+            SequenceStep::Tap {
+                key,
+                hold_ms,
+                hold_frames,
+                ..
+            } => {
+                let hold = hold_duration(self.sample(hold_ms), *hold_frames);
+                info!("Pulsando [{}] durante {:?}", key, hold);
+                self.hold_keys(&[keys::str_to_key(key)], hold, ctx)?;
+            }
+            SequenceStep::Chord {
+                keys: chord,
+                hold_ms,
+                hold_frames,
+                ..
+            } => {
+                let hold = hold_duration(self.sample(hold_ms), *hold_frames);
+                info!("Pulsando acorde {:?} durante {:?}", chord, hold);
+                let chord = chord
+                    .iter()
+                    .map(|key| keys::str_to_key(key))
+                    .collect::<Vec<_>>();
+                self.hold_keys(&chord, hold, ctx)?;
+            }
+            SequenceStep::TypeText {
+                text,
+                per_char_delay_ms,
+                ..
+            } => {
+                let layout = CONFIG.read().keyboard_layout;
+                info!("Escribiendo {:?} con distribución {}", text, layout);
 
-                let (tx, rx) = bounded(1);
-
-                let shutdown_rx = self.shutdown_rx.clone();
+                for c in text.chars() {
+                    if !self.is_live(ctx.run_id) {
+                        break;
+                    }
+                    let Some((k, level)) = keys::char_to_key(c, layout) else {
+                        warn!(
+                            "No se puede escribir {:?} con la distribución {}",
+                            c, layout
+                        );
+                        continue;
+                    };
 
-                let wait = *ms;
-                std::thread::spawn(move || {
-                    sleep(Duration::from_millis(wait));
-                    let _ = tx.send(());
-                });
+                    let modifier = level.modifier();
+                    if let Some(m) = modifier {
+                        self.send(EventType::KeyPress(m))?;
+                    }
+                    self.send(EventType::KeyPress(k))?;
+                    self.send(EventType::KeyRelease(k))?;
+                    if let Some(m) = modifier {
+                        self.send(EventType::KeyRelease(m))?;
+                    }
 
-                crossbeam::select! {
-                    recv(rx) -> _ => (),
-                    recv(shutdown_rx) -> _ => {
-                        info!("Delay interrupted by shutdown");
+                    if *per_char_delay_ms > 0 {
+                        self.wait(Duration::from_millis(*per_char_delay_ms), ctx)?;
+                    }
+                }
+            }
+            SequenceStep::MouseMove { x, y, relative, .. } => {
+                let (x, y) = if *relative {
+                    let Some((cx, cy)) = self.output.cursor() else {
+                        warn!("Posición del cursor desconocida, se omite el movimiento relativo");
                         return Ok(());
+                    };
+                    (cx + f64::from(*x), cy + f64::from(*y))
+                } else {
+                    (f64::from(*x), f64::from(*y))
+                };
+
+                info!("Moviendo cursor a ({}, {})", x, y);
+                self.send(EventType::MouseMove { x, y })?;
+                self.output.set_cursor((x, y));
+            }
+            SequenceStep::MouseDown { button, .. } => {
+                info!("Pulsando botón [{}]", button);
+                self.send(EventType::ButtonPress(Self::button(button)?))?;
+            }
+            SequenceStep::MouseUp { button, .. } => {
+                info!("Soltando botón [{}]", button);
+                self.send(EventType::ButtonRelease(Self::button(button)?))?;
+            }
+            SequenceStep::MouseClick { button, .. } => {
+                info!("Clic con [{}]", button);
+                let b = Self::button(button)?;
+                self.send(EventType::ButtonPress(b))?;
+                self.send(EventType::ButtonRelease(b))?;
+            }
+            SequenceStep::Scroll { dx, dy, .. } => {
+                info!("Rueda ({}, {})", dx, dy);
+                self.send(EventType::Wheel {
+                    delta_x: *dx,
+                    delta_y: *dy,
+                })?;
+            }
+            SequenceStep::WaitForKey {
+                key,
+                state,
+                timeout_ms,
+                on_timeout,
+                ..
+            } => {
+                if self.output.kind() == OutputKind::Recording {
+                    info!("Vista previa: se da por cumplida la espera de [{}]", key);
+                    return Ok(());
+                }
+                let target = keys::str_to_input(key);
+                let wanted = *state == WaitState::Pressed;
+                // Suscribirse antes de mirar el estado para no perder un cambio intermedio
+                let events = subscribe_input();
+                if KEY_STATE.lock().is_pressed(target) == wanted {
+                    return Ok(());
+                }
+
+                info!("Esperando [{}] {:?} hasta {} ms", key, state, timeout_ms);
+                let deadline = self.clock.after(Duration::from_millis(*timeout_ms));
+                let cancel_rx = self.cancel_rx(ctx.run_id);
+                loop {
+                    crossbeam::select! {
+                        recv(events) -> ev => match ev {
+                            Ok(ev) if transition(&ev) == Some((target, wanted)) => break,
+                            Ok(_) => {}
+                            Err(_) => bail!("El flujo de entrada se cerró"),
+                        },
+                        recv(deadline) -> _ => match on_timeout {
+                            OnTimeout::Continue => {
+                                info!("Tiempo agotado esperando [{}], se continúa", key);
+                                break;
+                            }
+                            OnTimeout::Abort => return Err(Aborted.into()),
+                        },
+                        recv(cancel_rx) -> _ => {
+                            info!("Wait interrupted by cancellation");
+                            return Ok(());
+                        }
+                    }
+                }
+                // La espera no se puede prever; el plan sigue desde aquí
+                recorder::resync();
+            }
+            SequenceStep::CallMacro { macro_id, .. } => {
+                if ctx.depth >= MAX_CALL_DEPTH {
+                    bail!("Se superó el máximo de {MAX_CALL_DEPTH} llamadas anidadas");
+                }
+                // Se lee en cada llamada para usar siempre la versión actual de la macro
+                let sequence = CONFIG
+                    .read()
+                    .macro_program(*macro_id)
+                    .with_context(|| format!("Macro {macro_id} no encontrada"))?;
+
+                info!("Llamando a la macro [{}]", macro_id);
+                self.run_steps(
+                    &sequence,
+                    RunContext {
+                        depth: ctx.depth + 1,
+                        ..ctx
+                    },
+                )?;
+            }
+            SequenceStep::Repeat { count, body, .. } => {
+                info!("Repitiendo {} veces", count);
+                for _ in 0..*count {
+                    if !self.is_live(ctx.run_id) {
+                        break;
+                    }
+                    self.run_steps(body, ctx)?;
+                }
+            }
+            SequenceStep::LoopUntilReleased { body, .. } => {
+                let Some(trigger) = ctx.trigger else {
+                    warn!("Bucle sin tecla de disparo, se ejecuta una sola vez");
+                    return self.run_steps(body, ctx);
+                };
+                if body.is_empty() {
+                    return Ok(());
+                }
+
+                info!("Repitiendo mientras [{:?}] siga pulsada", trigger);
+                loop {
+                    self.run_steps(body, ctx)?;
+                    if !self.is_live(ctx.run_id) || !KEY_STATE.lock().is_pressed(trigger) {
+                        break;
                     }
                 }
             }
+            SequenceStep::If {
+                condition,
+                then,
+                else_,
+                ..
+            } => {
+                let holds = self.evaluate(condition, ctx);
+                info!("Condición {:?}: {}", condition, holds);
+                self.run_steps(if holds { then } else { else_ }, ctx)?;
+            }
+            SequenceStep::SetFacing { side, .. } => {
+                info!("Mirando hacia {:?}", side);
+                self.runtime.set_facing(*side);
+            }
+            SequenceStep::SetVar { name, value, .. } => {
+                info!("Variable [{}] = {:?}", name, value);
+                self.runtime.set_variable(ctx.profile_id, name, *value);
+            }
+            SequenceStep::IncrementVar { name, by, wrap, .. } => {
+                let value = self.runtime.increment(ctx.profile_id, name, *by, *wrap)?;
+                info!("Variable [{}] = {}", name, value);
+            }
+            SequenceStep::Script { source, .. } => {
+                info!("Ejecutando script de {} bytes", source.len());
+                script::run(&self.script_host(ctx), source)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Evalúa la condición con el estado actual, sin esperar a ningún evento.
+    fn evaluate(&self, condition: &Condition, ctx: RunContext) -> bool {
+        match condition {
+            Condition::Held { key } => {
+                let state = KEY_STATE.lock();
+                keys::str_to_held_inputs(key)
+                    .into_iter()
+                    .any(|k| state.is_pressed(k))
+            }
+            Condition::Facing { side } => self.runtime.facing() == *side,
+            Condition::Var { name, op, value } => {
+                op.test(self.runtime.variable(ctx.profile_id, name), *value)
+            }
+            Condition::Not { condition } => !self.evaluate(condition, ctx),
+            Condition::All { conditions } => conditions.iter().all(|c| self.evaluate(c, ctx)),
+            Condition::Any { conditions } => conditions.iter().any(|c| self.evaluate(c, ctx)),
+        }
+    }
+
+    fn script_host(&self, ctx: RunContext) -> ScriptHost {
+        ScriptHost {
+            cancel_rx: self.cancel_rx(ctx.run_id),
+            clock: self.clock.clone(),
+            runtime: self.runtime.clone(),
+            output: self.output.clone(),
+            profile_id: ctx.profile_id,
+        }
+    }
+
     #[inline]
-    pub fn run_sequence(&self, sequence: &[SequenceStep]) -> Result<()> {
-        info!("Starting sequence execution - {} steps", sequence.len());
-        if !self.active.load(Ordering::SeqCst) {
-            return Err(anyhow::anyhow!("Executor is shutting down"));
+    pub fn runtime(&self) -> &RuntimeState {
+        &self.runtime
+    }
+
+    fn button(name: &str) -> Result<rdev::Button> {
+        keys::str_to_button(name).with_context(|| format!("Botón desconocido: {name}"))
+    }
+
+    /// Pulsa `keys` en orden, espera `hold` y las suelta en orden inverso.
+    ///
+    /// Las que se intentaron pulsar se sueltan siempre, aunque falle una
+    /// pulsación, otra liberación o se interrumpa la espera, para no dejar
+    /// teclas pegadas; se devuelve el primer error.
+    fn hold_keys(&self, keys: &[rdev::Key], hold: Duration, ctx: RunContext) -> Result<()> {
+        let mut attempted = 0;
+        let mut result = Ok(());
+        for k in keys {
+            attempted += 1;
+            if let Err(e) = self.send(EventType::KeyPress(*k)) {
+                result = Err(e);
+                break;
+            }
         }
 
-        let sequence_arc = Arc::new(sequence.to_vec());
-        *self.current_sequence.lock() = Some(sequence_arc.clone());
+        if result.is_ok() {
+            result = self.wait(hold, ctx);
+        }
+
+        for k in keys[..attempted].iter().rev() {
+            if let Err(e) = self.send(EventType::KeyRelease(*k)) {
+                warn!("No se pudo soltar {:?}: {:#}", k, e);
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Espera `duration`; si se cancela la ejecución devuelve [`Aborted`] y si
+    /// la salida corta la espera, su error. La espera cuenta como prevista en
+    /// el informe de tiempos.
+    fn wait(&self, duration: Duration, ctx: RunContext) -> Result<()> {
+        recorder::plan(duration);
+        if self
+            .output
+            .wait(&*self.clock, duration, &self.cancel_rx(ctx.run_id))?
+        {
+            Ok(())
+        } else {
+            Err(Aborted.into())
+        }
+    }
+
+    /// Reserva un id de ejecución en estado `Queued` para poder cancelarla o
+    /// consultarla antes de que empiece.
+    pub fn register_run(&self) -> u64 {
+        let run_id = self.next_run_id.fetch_add(1, Ordering::SeqCst);
+        let (cancel_tx, cancel_rx) = bounded(0);
+        self.runs.lock().entries.insert(
+            run_id,
+            RunEntry {
+                status: RunStatus::Queued,
+                cancel_tx: Some(cancel_tx),
+                cancel_rx,
+            },
+        );
+        run_id
+    }
+
+    /// Cancela una ejecución encolada o en curso; `false` si ya terminó, ya
+    /// se había cancelado o no existe.
+    pub fn cancel_run(&self, run_id: u64) -> bool {
+        let mut runs = self.runs.lock();
+        match runs.entries.get_mut(&run_id) {
+            Some(entry) if !entry.status.is_finished() => {
+                info!("Cancelando la ejecución {}", run_id);
+                entry.cancel_tx.take().is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// Estado de la ejecución; las terminadas se olvidan pasadas [`MAX_FINISHED_RUNS`].
+    pub fn run_status(&self, run_id: u64) -> Option<RunStatus> {
+        self.runs
+            .lock()
+            .entries
+            .get(&run_id)
+            .map(|entry| entry.status.clone())
+    }
+
+    pub(crate) fn set_status(&self, run_id: u64, status: RunStatus) {
+        let mut runs = self.runs.lock();
+        let finished = status.is_finished();
+        let Some(entry) = runs.entries.get_mut(&run_id) else {
+            return;
+        };
+        entry.status = status;
+        if !finished {
+            return;
+        }
+
+        entry.cancel_tx = None;
+        runs.finished.push_back(run_id);
+        while runs.finished.len() > MAX_FINISHED_RUNS {
+            if let Some(old) = runs.finished.pop_front() {
+                runs.entries.remove(&old);
+            }
+        }
+    }
+
+    /// Se desconecta al cancelar la ejecución o al apagar el ejecutor.
+    fn cancel_rx(&self, run_id: u64) -> Receiver<()> {
+        self.runs
+            .lock()
+            .entries
+            .get(&run_id)
+            .map_or_else(never, |entry| entry.cancel_rx.clone())
+    }
+
+    /// Ni el ejecutor se está apagando ni se canceló la ejecución.
+    fn is_live(&self, run_id: u64) -> bool {
+        self.active.load(Ordering::SeqCst)
+            && self
+                .runs
+                .lock()
+                .entries
+                .get(&run_id)
+                .is_some_and(|entry| entry.cancel_tx.is_some())
+    }
+
+    #[inline]
+    fn send(&self, ev: EventType) -> Result<()> {
+        self.output.send(ev)
+    }
+
+    /// La vista previa no se publica ni cuenta en el historial de tiempos.
+    #[inline]
+    fn publish(&self, event: RunEvent) {
+        if self.output.kind() != OutputKind::Recording {
+            run_events::publish(event);
+        }
+    }
 
+    fn run_steps(&self, sequence: &[SequenceStep], ctx: RunContext) -> Result<()> {
+        let inner = RunContext {
+            nesting: ctx.nesting + 1,
+            ..ctx
+        };
         for (i, step) in sequence.iter().enumerate() {
             info!("Executing step {}: {:?}", i, step);
-            if !self.active.load(Ordering::SeqCst) {
+            if !self.is_live(ctx.run_id) {
                 break;
             }
-            self.execute(step)?;
+
+            let (step_id, index, depth) = (step.id(), i, ctx.nesting);
+            self.publish(RunEvent::StepStarted {
+                run_id: ctx.run_id,
+                step_id,
+                index,
+                depth,
+                at_us: self.clock.now_us(),
+            });
+            recorder::step_started(step_id, depth);
+            self.execute(step, inner)?;
+            recorder::step_finished();
+            self.publish(RunEvent::StepFinished {
+                run_id: ctx.run_id,
+                step_id,
+                index,
+                depth,
+                at_us: self.clock.now_us(),
+            });
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn run_sequence(&self, sequence: &[SequenceStep], source: RunSource) -> Result<()> {
+        info!("Starting sequence execution - {} steps", sequence.len());
+        let run_id = source.run_id.unwrap_or_else(|| self.register_run());
+        if !self.active.load(Ordering::SeqCst) {
+            self.set_status(
+                run_id,
+                RunStatus::Finished {
+                    outcome: RunOutcome::Cancelled,
+                    error: None,
+                },
+            );
+            return Err(anyhow::anyhow!("Executor is shutting down"));
+        }
+
+        if !source.delay_before.is_zero() {
+            info!(
+                "Esperando {:?} antes de la ejecución {}",
+                source.delay_before, run_id
+            );
+            if let Err(e) =
+                self.output
+                    .wait(&*self.clock, source.delay_before, &self.cancel_rx(run_id))
+            {
+                self.set_status(
+                    run_id,
+                    RunStatus::Finished {
+                        outcome: RunOutcome::Failed,
+                        error: Some(e.to_string()),
+                    },
+                );
+                return Err(e);
+            }
+        }
+        if !self.is_live(run_id) {
+            info!("Ejecución {} cancelada antes de empezar", run_id);
+            self.set_status(
+                run_id,
+                RunStatus::Finished {
+                    outcome: RunOutcome::Cancelled,
+                    error: None,
+                },
+            );
+            return Ok(());
+        }
+
+        self.set_status(run_id, RunStatus::Running);
+        self.publish(RunEvent::RunStarted {
+            run_id,
+            macro_id: source.macro_id,
+            at_us: self.clock.now_us(),
+        });
+        // Solo las teclas reales miden la precisión de la reproducción
+        if self.output.kind() == OutputKind::System {
+            recorder::begin(
+                run_id,
+                source.macro_id,
+                source.triggered_at_us,
+                self.clock.clone(),
+            );
         }
 
+        let sequence_arc = Arc::new(sequence.to_vec());
+        *self.current_sequence.lock() = Some(sequence_arc.clone());
+
+        let result = self.run_steps(
+            sequence,
+            RunContext {
+                run_id,
+                trigger: source.trigger,
+                depth: 0,
+                profile_id: source.profile_id,
+                nesting: 0,
+            },
+        );
+
         *self.current_sequence.lock() = None;
+        // Con la macro ya terminada, escribir no retrasa ninguna pulsación
+        if let Err(e) = self.runtime.flush() {
+            warn!("No se pudieron guardar las variables: {e:?}");
+        }
 
-        info!("Sequence execution completed");
+        let (outcome, error) = match &result {
+            Ok(()) if !self.is_live(run_id) => (RunOutcome::Cancelled, None),
+            Ok(()) => (RunOutcome::Completed, None),
+            Err(e) if e.is::<Aborted>() => (RunOutcome::Cancelled, None),
+            Err(e) => (RunOutcome::Failed, Some(e.to_string())),
+        };
+        recorder::finish(outcome);
+        self.set_status(
+            run_id,
+            RunStatus::Finished {
+                outcome,
+                error: error.clone(),
+            },
+        );
+        self.publish(RunEvent::RunFinished {
+            run_id,
+            outcome,
+            error,
+            at_us: self.clock.now_us(),
+        });
+
+        match result {
+            Err(e) if e.is::<Aborted>() => info!("Sequence aborted"),
+            result => {
+                result?;
+                info!("Sequence execution completed");
+            }
+        }
         Ok(())
     }
 
     pub fn shutdown(&self) {
         self.active.store(false, Ordering::SeqCst);
-        let _ = self.shutdown_tx.send(());
+        // Sin los emisores se cortan todas las esperas de todas las ejecuciones
+        for entry in self.runs.lock().entries.values_mut() {
+            entry.cancel_tx = None;
+        }
 
         if let Some(sequence) = self.current_sequence.lock().take() {
             info!("Interrupting ongoing sequence of {} steps", sequence.len());
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::{
+        domain::{
+            preview::{OutputEvent, TimelineEntry},
+            variables::{Compare, VarValue},
+        },
+        engine::{clock::ManualClock, output::RecordingOutput},
+        input::state::CURSOR,
+    };
+
+    /// Ejecuta `steps` en tiempo virtual y devuelve la línea de tiempo.
+    fn record(seed: u64, steps: &[SequenceStep]) -> (Vec<TimelineEntry>, Duration) {
+        let output = Arc::new(RecordingOutput::default());
+        let executor = Executor::with_seed(Some(seed))
+            .detached(output.clone())
+            .with_clock(output.clock());
+        executor
+            .run_sequence(steps, RunSource::default())
+            .expect("la secuencia debería terminar");
+        output.finish()
+    }
+
+    fn down(at_us: u64, key: &str) -> TimelineEntry {
+        TimelineEntry {
+            at_us,
+            event: OutputEvent::KeyDown { key: key.into() },
+        }
+    }
+
+    fn up(at_us: u64, key: &str) -> TimelineEntry {
+        TimelineEntry {
+            at_us,
+            event: OutputEvent::KeyUp { key: key.into() },
+        }
+    }
+
+    fn tap(key: &str, hold_ms: Timing, hold_frames: Option<u32>) -> SequenceStep {
+        SequenceStep::Tap {
+            id: 0,
+            key: key.into(),
+            hold_ms,
+            hold_frames,
+        }
+    }
+
+    fn delay(ms: Timing) -> SequenceStep {
+        SequenceStep::Delay { id: 0, ms }
+    }
+
+    #[test]
+    fn delay_separates_events_in_virtual_time() {
+        let (timeline, total) = record(
+            1,
+            &[
+                SequenceStep::KeyDown {
+                    id: 0,
+                    key: "A".into(),
+                },
+                delay(Timing::Fixed(250)),
+                SequenceStep::KeyUp {
+                    id: 0,
+                    key: "A".into(),
+                },
+            ],
+        );
+
+        assert_eq!(timeline, vec![down(0, "KeyA"), up(250_000, "KeyA")]);
+        assert_eq!(total, Duration::from_millis(250));
+    }
+
+    #[test]
+    fn tap_holds_for_ms_or_frames() {
+        let (timeline, _) = record(1, &[tap("A", Timing::Fixed(80), None)]);
+        assert_eq!(timeline, vec![down(0, "KeyA"), up(80_000, "KeyA")]);
+
+        // `hold_frames` manda sobre `hold_ms`: 3 fotogramas a 60 fps son 50 ms
+        let (timeline, _) = record(1, &[tap("A", Timing::Fixed(80), Some(3))]);
+        assert_eq!(timeline, vec![down(0, "KeyA"), up(50_000, "KeyA")]);
+    }
+
+    #[test]
+    fn double_tap_keeps_its_gap() {
+        let (timeline, total) = record(
+            1,
+            &[
+                tap("A", Timing::Fixed(30), None),
+                delay(Timing::Fixed(50)),
+                tap("A", Timing::Fixed(30), None),
+            ],
+        );
+
+        assert_eq!(
+            timeline,
+            vec![
+                down(0, "KeyA"),
+                up(30_000, "KeyA"),
+                down(80_000, "KeyA"),
+                up(110_000, "KeyA"),
+            ]
+        );
+        assert_eq!(total, Duration::from_millis(110));
+    }
+
+    fn mouse_move(x: i32, y: i32, relative: bool) -> SequenceStep {
+        SequenceStep::MouseMove {
+            id: 1,
+            x,
+            y,
+            relative,
+        }
+    }
+
+    #[test]
+    fn preview_moves_its_own_cursor() {
+        let real = *CURSOR.lock();
+        let output = Arc::new(RecordingOutput::starting_at(Some((100.0, 100.0))));
+        let executor = Executor::with_seed(Some(1)).detached(output.clone());
+        executor
+            .run_sequence(
+                &[
+                    mouse_move(5, -5, true),
+                    mouse_move(10, 20, false),
+                    mouse_move(5, 5, true),
+                ],
+                RunSource::default(),
+            )
+            .unwrap();
+
+        let moves: Vec<_> = output.finish().0.into_iter().map(|e| e.event).collect();
+        assert_eq!(
+            moves,
+            vec![
+                OutputEvent::MouseMove { x: 105.0, y: 95.0 },
+                OutputEvent::MouseMove { x: 10.0, y: 20.0 },
+                OutputEvent::MouseMove { x: 15.0, y: 25.0 },
+            ]
+        );
+        assert_eq!(output.cursor(), Some((15.0, 25.0)));
+        assert_eq!(*CURSOR.lock(), real);
+    }
+
+    #[test]
+    fn relative_moves_need_a_known_cursor() {
+        let (timeline, _) = record(1, &[mouse_move(5, 5, true), mouse_move(1, 2, false)]);
+
+        assert_eq!(
+            timeline,
+            vec![TimelineEntry {
+                at_us: 0,
+                event: OutputEvent::MouseMove { x: 1.0, y: 2.0 },
+            }]
+        );
+    }
+
+    #[test]
+    fn same_seed_samples_same_timings() {
+        let steps = [
+            tap("A", Timing::Uniform { min: 20, max: 60 }, None),
+            delay(Timing::Normal {
+                mean: 100,
+                stddev: 30,
+                min: Some(40),
+                max: None,
+            }),
+            tap("B", Timing::Uniform { min: 20, max: 60 }, None),
+        ];
+
+        assert_eq!(record(7, &steps), record(7, &steps));
+    }
+
+    #[test]
+    fn cancelled_before_start_sends_nothing() {
+        let output = Arc::new(RecordingOutput::default());
+        let executor = Executor::with_seed(Some(1))
+            .detached(output.clone())
+            .with_clock(output.clock());
+
+        let run_id = executor.register_run();
+        assert!(executor.cancel_run(run_id));
+        executor
+            .run_sequence(
+                &[tap("A", Timing::Fixed(30), None)],
+                RunSource {
+                    run_id: Some(run_id),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            executor.run_status(run_id),
+            Some(RunStatus::Finished {
+                outcome: RunOutcome::Cancelled,
+                error: None,
+            })
+        );
+        assert_eq!(output.finish(), (Vec::new(), Duration::ZERO));
+    }
+
+    /// Avisa por `armed` cada vez que empieza una espera.
+    #[derive(Debug)]
+    struct ArmedOutput {
+        armed: Sender<()>,
+    }
+
+    impl OutputBackend for ArmedOutput {
+        fn kind(&self) -> OutputKind {
+            OutputKind::Simulated
+        }
+
+        fn send(&self, _ev: EventType) -> Result<()> {
+            Ok(())
+        }
+
+        fn wait(
+            &self,
+            clock: &dyn Clock,
+            duration: Duration,
+            cancel_rx: &Receiver<()>,
+        ) -> Result<bool> {
+            let _ = self.armed.send(());
+            Ok(clock.sleep(duration, cancel_rx))
+        }
+
+        fn cursor(&self) -> Option<(f64, f64)> {
+            None
+        }
+
+        fn set_cursor(&self, _position: (f64, f64)) {}
+    }
+
+    #[test]
+    fn cancel_cuts_a_delay_without_advancing_the_clock() {
+        let clock = Arc::new(ManualClock::default());
+        let (armed_tx, armed_rx) = crossbeam_channel::unbounded();
+        let executor = Arc::new(
+            Executor::with_seed(Some(1))
+                .detached(Arc::new(ArmedOutput { armed: armed_tx }))
+                .with_clock(clock.clone()),
+        );
+        let run_id = executor.register_run();
+
+        let runner = {
+            let executor = executor.clone();
+            thread::spawn(move || {
+                executor.run_sequence(
+                    &[delay(Timing::Fixed(60_000))],
+                    RunSource {
+                        run_id: Some(run_id),
+                        ..Default::default()
+                    },
+                )
+            })
+        };
+
+        // El reloj manual no avanza solo: la espera no acaba hasta que se cancele
+        armed_rx.recv().unwrap();
+        assert_eq!(executor.run_status(run_id), Some(RunStatus::Running));
+        assert!(executor.cancel_run(run_id));
+        runner.join().unwrap().unwrap();
+
+        assert_eq!(
+            executor.run_status(run_id),
+            Some(RunStatus::Finished {
+                outcome: RunOutcome::Cancelled,
+                error: None,
+            })
+        );
+        assert_eq!(clock.now_us(), 0);
+    }
+
+    /// Anota lo que se envía y falla con los eventos de `fail_on`, que no se anotan.
+    #[derive(Debug, Default)]
+    struct FlakyOutput {
+        fail_on: Vec<OutputEvent>,
+        sent: Mutex<Vec<OutputEvent>>,
+    }
+
+    impl OutputBackend for FlakyOutput {
+        fn kind(&self) -> OutputKind {
+            OutputKind::Recording
+        }
+
+        fn send(&self, ev: EventType) -> Result<()> {
+            let event = OutputEvent::from(&ev);
+            if self.fail_on.contains(&event) {
+                bail!("Fallo simulado con {event:?}");
+            }
+            self.sent.lock().push(event);
+            Ok(())
+        }
+
+        fn wait(
+            &self,
+            _clock: &dyn Clock,
+            _duration: Duration,
+            _cancel_rx: &Receiver<()>,
+        ) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn cursor(&self) -> Option<(f64, f64)> {
+            None
+        }
+
+        fn set_cursor(&self, _position: (f64, f64)) {}
+    }
+
+    /// Ejecuta `step` con una salida que falla en `fail_on` y devuelve lo enviado.
+    fn run_flaky(step: SequenceStep, fail_on: Vec<OutputEvent>) -> Vec<OutputEvent> {
+        let output = Arc::new(FlakyOutput {
+            fail_on,
+            ..Default::default()
+        });
+        let executor = Executor::with_seed(Some(1)).detached(output.clone());
+        assert!(executor
+            .run_sequence(&[step], RunSource::default())
+            .is_err());
+        let sent = std::mem::take(&mut *output.sent.lock());
+        sent
+    }
+
+    fn key_down(key: &str) -> OutputEvent {
+        OutputEvent::KeyDown { key: key.into() }
+    }
+
+    fn key_up(key: &str) -> OutputEvent {
+        OutputEvent::KeyUp { key: key.into() }
+    }
+
+    fn chord(keys: &[&str]) -> SequenceStep {
+        SequenceStep::Chord {
+            id: 0,
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            hold_ms: Timing::Fixed(30),
+            hold_frames: None,
+        }
+    }
+
+    #[test]
+    fn chord_releases_pressed_keys_when_a_press_fails() {
+        let sent = run_flaky(chord(&["A", "B", "C"]), vec![key_down("KeyB")]);
+        assert_eq!(sent, vec![key_down("KeyA"), key_up("KeyB"), key_up("KeyA")]);
+    }
+
+    #[test]
+    fn chord_keeps_releasing_when_a_release_fails() {
+        let sent = run_flaky(chord(&["A", "B", "C"]), vec![key_up("KeyB")]);
+        assert_eq!(
+            sent,
+            vec![
+                key_down("KeyA"),
+                key_down("KeyB"),
+                key_down("KeyC"),
+                key_up("KeyC"),
+                key_up("KeyA"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tap_releases_when_the_press_fails() {
+        let sent = run_flaky(tap("A", Timing::Fixed(30), None), vec![key_down("KeyA")]);
+        assert_eq!(sent, vec![key_up("KeyA")]);
+    }
+
+    /// Pulsa `X` si se cumple `condition` y `Y` si no, tras fijar `hits`.
+    fn branch(condition: Condition) -> Vec<SequenceStep> {
+        vec![
+            SequenceStep::SetVar {
+                id: 0,
+                name: "hits".into(),
+                value: VarValue::Int(2),
+            },
+            SequenceStep::If {
+                id: 0,
+                condition,
+                then: vec![tap("X", Timing::Fixed(0), None)],
+                else_: vec![tap("Y", Timing::Fixed(0), None)],
+            },
+        ]
+    }
+
+    fn taken(condition: Condition) -> &'static str {
+        let (timeline, _) = record(1, &branch(condition));
+        match &timeline[0].event {
+            OutputEvent::KeyDown { key } if key == "KeyX" => "then",
+            OutputEvent::KeyDown { key } if key == "KeyY" => "else",
+            other => panic!("evento inesperado: {other:?}"),
+        }
+    }
+
+    fn hits(op: Compare, value: i64) -> Condition {
+        Condition::Var {
+            name: "hits".into(),
+            op,
+            value: VarValue::Int(value),
+        }
+    }
+
+    #[test]
+    fn if_follows_variable_conditions() {
+        assert_eq!(taken(hits(Compare::Eq, 2)), "then");
+        assert_eq!(taken(hits(Compare::Gt, 2)), "else");
+        assert_eq!(
+            taken(Condition::Var {
+                name: "otra".into(),
+                op: Compare::Eq,
+                value: VarValue::Int(0),
+            }),
+            "then"
+        );
+    }
+
+    #[test]
+    fn if_combines_nested_conditions() {
+        let not = |c| Condition::Not {
+            condition: Box::new(c),
+        };
+        assert_eq!(taken(not(hits(Compare::Eq, 2))), "else");
+        assert_eq!(
+            taken(Condition::All {
+                conditions: vec![hits(Compare::Ge, 2), not(hits(Compare::Lt, 1))],
+            }),
+            "then"
+        );
+        assert_eq!(
+            taken(Condition::Any {
+                conditions: vec![hits(Compare::Lt, 0), hits(Compare::Ne, 2)],
+            }),
+            "else"
+        );
+        assert_eq!(taken(Condition::default()), "then");
+        assert_eq!(
+            taken(Condition::Any {
+                conditions: Vec::new()
+            }),
+            "else"
+        );
+    }
+}
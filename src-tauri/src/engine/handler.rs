@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use anyhow::{Context, Result};
 
@@ -6,10 +6,10 @@ use crossbeam_channel::bounded;
 use tracing::{error, info};
 
 use crate::{
-    config::model::StoredMacro,
+    domain::run::{RunOutcome, RunStatus},
     engine::{
         executor::Executor,
-        queue::{MacroQueue, QueueCommand},
+        queue::{MacroQueue, QueueCommand, QueuedRun, RunJob},
     },
 };
 
@@ -17,6 +17,7 @@ use crate::{
 pub struct QueueHandler {
     tx: crossbeam_channel::Sender<QueueCommand>,
     shutdown_tx: crossbeam_channel::Sender<()>,
+    executor: Arc<Executor>,
 }
 
 impl QueueHandler {
@@ -26,7 +27,7 @@ impl QueueHandler {
         let (tx, rx) = bounded(1024);
         let (shutdown_tx, shutdown_rx) = bounded(1);
 
-        let worker = MacroQueue::new(rx, shutdown_rx, executor);
+        let worker = MacroQueue::new(rx, shutdown_rx, executor.clone());
         let handle = std::thread::Builder::new()
             .name("macro_queue_worker".into())
             .spawn(move || {
@@ -36,14 +37,45 @@ impl QueueHandler {
             })
             .expect("No se pudo spawnear el thread del QueueHandler");
 
-        (QueueHandler { tx, shutdown_tx }, handle)
+        (
+            QueueHandler {
+                tx,
+                shutdown_tx,
+                executor,
+            },
+            handle,
+        )
     }
 
-    pub fn push(&self, macro_def: StoredMacro) -> Result<()> {
-        info!("Intentando enviar macro: {}", macro_def.name); // ← Agregar este log
-        self.tx
-            .try_send(QueueCommand::Push(macro_def))
-            .context("El canal está lleno o el worker se detuvo")
+    /// Encola el trabajo y devuelve el id con el que cancelarlo o consultar su
+    /// estado. `triggered_at_us` es cuándo se pulsó el disparador, en el reloj
+    /// del ejecutor, para medir la latencia.
+    pub fn push(
+        &self,
+        job: RunJob,
+        delay_before: Duration,
+        triggered_at_us: Option<u64>,
+    ) -> Result<u64> {
+        let run_id = self.executor.register_run();
+        info!("Intentando encolar la ejecución {}", run_id);
+
+        let run = QueuedRun {
+            run_id,
+            job,
+            delay_before,
+            triggered_at_us,
+        };
+        if let Err(e) = self.tx.try_send(QueueCommand::Push(run)) {
+            self.executor.set_status(
+                run_id,
+                RunStatus::Finished {
+                    outcome: RunOutcome::Failed,
+                    error: Some("No se pudo encolar".into()),
+                },
+            );
+            return Err(e).context("El canal está lleno o el worker se detuvo");
+        }
+        Ok(run_id)
     }
 
     pub fn shutdown(&self) {
@@ -1,5 +1,13 @@
+pub mod clock;
+pub mod commands;
 pub mod event;
 pub mod executor;
 pub mod handler;
+pub mod output;
+pub mod preview;
 pub mod processor;
 pub mod queue;
+pub mod recorder;
+pub mod run_events;
+pub mod runtime;
+pub mod script;
@@ -0,0 +1,231 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use crossbeam_channel::Receiver;
+use parking_lot::Mutex;
+use rdev::EventType;
+
+use crate::{
+    domain::{
+        preview::{OutputEvent, TimelineEntry},
+        run::RunEvent,
+    },
+    engine::{
+        clock::{Clock, ManualClock},
+        event::send_event,
+        run_events::{self, now_us},
+    },
+    input::state::CURSOR,
+};
+
+/// Límite de tiempo virtual de una vista previa.
+pub const MAX_PREVIEW_DURATION: Duration = Duration::from_secs(10 * 60);
+/// Límite de eventos anotados en una vista previa.
+pub const MAX_PREVIEW_EVENTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputKind {
+    /// Teclas reales hacia el sistema.
+    System,
+    /// En tiempo real, pero los eventos solo se muestran en la UI.
+    Simulated,
+    /// En tiempo virtual; los eventos solo se anotan.
+    Recording,
+}
+
+/// Destino de los eventos que genera el ejecutor y reloj de sus esperas.
+pub trait OutputBackend: Send + Sync + fmt::Debug {
+    fn kind(&self) -> OutputKind;
+
+    fn send(&self, ev: EventType) -> Result<()>;
+
+    /// Espera `duration` en `clock`; devuelve `false` si `cancel_rx` la cortó
+    /// antes y un error si la salida no admite seguir esperando.
+    fn wait(&self, clock: &dyn Clock, duration: Duration, cancel_rx: &Receiver<()>)
+        -> Result<bool>;
+
+    /// Posición del cursor tras los movimientos enviados, para los relativos.
+    fn cursor(&self) -> Option<(f64, f64)>;
+
+    fn set_cursor(&self, position: (f64, f64));
+}
+
+#[derive(Debug, Default)]
+pub struct SystemOutput;
+
+impl OutputBackend for SystemOutput {
+    fn kind(&self) -> OutputKind {
+        OutputKind::System
+    }
+
+    fn send(&self, ev: EventType) -> Result<()> {
+        send_event(ev)
+    }
+
+    fn wait(
+        &self,
+        clock: &dyn Clock,
+        duration: Duration,
+        cancel_rx: &Receiver<()>,
+    ) -> Result<bool> {
+        Ok(clock.sleep(duration, cancel_rx))
+    }
+
+    /// La del cursor real, que también actualiza el listener.
+    fn cursor(&self) -> Option<(f64, f64)> {
+        *CURSOR.lock()
+    }
+
+    fn set_cursor(&self, position: (f64, f64)) {
+        *CURSOR.lock() = Some(position);
+    }
+}
+
+/// Emite cada evento a la UI como [`RunEvent::Output`] en lugar de enviarlo.
+#[derive(Debug, Default)]
+pub struct SimulatedOutput {
+    /// Cursor propio; el real no se mueve.
+    cursor: Mutex<Option<(f64, f64)>>,
+}
+
+impl SimulatedOutput {
+    /// Parte del cursor en `cursor`, p. ej. la posición real al empezar.
+    pub fn starting_at(cursor: Option<(f64, f64)>) -> Self {
+        Self {
+            cursor: Mutex::new(cursor),
+        }
+    }
+}
+
+impl OutputBackend for SimulatedOutput {
+    fn kind(&self) -> OutputKind {
+        OutputKind::Simulated
+    }
+
+    fn send(&self, ev: EventType) -> Result<()> {
+        run_events::publish(RunEvent::Output {
+            event: OutputEvent::from(&ev),
+            at_us: now_us(),
+        });
+        Ok(())
+    }
+
+    fn wait(
+        &self,
+        clock: &dyn Clock,
+        duration: Duration,
+        cancel_rx: &Receiver<()>,
+    ) -> Result<bool> {
+        Ok(clock.sleep(duration, cancel_rx))
+    }
+
+    fn cursor(&self) -> Option<(f64, f64)> {
+        *self.cursor.lock()
+    }
+
+    fn set_cursor(&self, position: (f64, f64)) {
+        *self.cursor.lock() = Some(position);
+    }
+}
+
+/// Anota los eventos con un reloj virtual que las esperas adelantan al instante.
+#[derive(Debug, Default)]
+pub struct RecordingOutput {
+    /// Sus marcas son relativas al inicio de la vista previa.
+    clock: Arc<ManualClock>,
+    timeline: Mutex<Vec<TimelineEntry>>,
+    /// Si se cortó al pasar alguno de los límites.
+    truncated: AtomicBool,
+    /// Cursor propio; el real no se mueve.
+    cursor: Mutex<Option<(f64, f64)>>,
+}
+
+impl RecordingOutput {
+    /// Parte del cursor en `cursor`, p. ej. la posición real al empezar.
+    pub fn starting_at(cursor: Option<(f64, f64)>) -> Self {
+        Self {
+            cursor: Mutex::new(cursor),
+            ..Default::default()
+        }
+    }
+
+    /// Reloj virtual que adelantan las esperas, para que el ejecutor lo comparta.
+    #[inline]
+    pub fn clock(&self) -> Arc<ManualClock> {
+        self.clock.clone()
+    }
+
+    /// Línea de tiempo anotada y tiempo virtual total.
+    pub fn finish(&self) -> (Vec<TimelineEntry>, Duration) {
+        let timeline = std::mem::take(&mut *self.timeline.lock());
+        (timeline, Duration::from_micros(self.clock.now_us()))
+    }
+
+    /// Si la macro no llegó a terminar por pasar [`MAX_PREVIEW_EVENTS`] o
+    /// [`MAX_PREVIEW_DURATION`].
+    #[inline]
+    pub fn truncated(&self) -> bool {
+        self.truncated.load(Ordering::SeqCst)
+    }
+
+    fn truncate(&self, limit: String) -> anyhow::Error {
+        self.truncated.store(true, Ordering::SeqCst);
+        anyhow!("La vista previa supera los {limit}")
+    }
+}
+
+fn duration_limit() -> String {
+    format!("{} minutos", MAX_PREVIEW_DURATION.as_secs() / 60)
+}
+
+impl OutputBackend for RecordingOutput {
+    fn kind(&self) -> OutputKind {
+        OutputKind::Recording
+    }
+
+    fn send(&self, ev: EventType) -> Result<()> {
+        let mut timeline = self.timeline.lock();
+        if timeline.len() >= MAX_PREVIEW_EVENTS {
+            return Err(self.truncate(format!("{MAX_PREVIEW_EVENTS} eventos")));
+        }
+        let at_us = self.clock.now_us();
+        if Duration::from_micros(at_us) >= MAX_PREVIEW_DURATION {
+            return Err(self.truncate(duration_limit()));
+        }
+        timeline.push(TimelineEntry {
+            at_us,
+            event: OutputEvent::from(&ev),
+        });
+        Ok(())
+    }
+
+    /// Adelanta su propio reloj sin esperar de verdad; la espera que pase de
+    /// [`MAX_PREVIEW_DURATION`] llega hasta el límite y falla.
+    fn wait(
+        &self,
+        _clock: &dyn Clock,
+        duration: Duration,
+        _cancel_rx: &Receiver<()>,
+    ) -> Result<bool> {
+        let left = MAX_PREVIEW_DURATION.saturating_sub(Duration::from_micros(self.clock.now_us()));
+        self.clock.advance(duration.min(left));
+        if duration > left {
+            return Err(self.truncate(duration_limit()));
+        }
+        Ok(true)
+    }
+    fn cursor(&self) -> Option<(f64, f64)> {
+        *self.cursor.lock()
+    }
+
+    fn set_cursor(&self, position: (f64, f64)) {
+        *self.cursor.lock() = Some(position);
+    }
+}
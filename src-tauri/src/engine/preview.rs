@@ -0,0 +1,210 @@
+use std::{
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+use anyhow::{Context, Result};
+use tracing::{error, info};
+
+use crate::{
+    config::handler::CONFIG,
+    domain::{
+        preview::Preview,
+        run::{RunOutcome, RunStatus},
+        sequence_step::SequenceStep,
+    },
+    engine::{
+        executor::{Executor, RunSource},
+        output::{OutputBackend, RecordingOutput, SimulatedOutput},
+    },
+    input::state::CURSOR,
+};
+
+/// Pasos y origen de la macro, leídos sin retener la configuración durante la ejecución.
+fn program(id: u64) -> Result<(Vec<SequenceStep>, RunSource)> {
+    let cfg = CONFIG.read();
+    let program = cfg
+        .macro_program(id)
+        .with_context(|| format!("Macro {id} no encontrada"))?;
+    let source = RunSource {
+        macro_id: Some(id),
+        profile_id: cfg.profile_of_macro(id),
+        ..Default::default()
+    };
+    Ok((program, source))
+}
+
+/// Ejecuta la macro en tiempo virtual y devuelve lo que habría enviado. Usa
+/// una copia de las variables, así que no cambia el estado real.
+pub fn preview_macro(live: &Executor, id: u64) -> Result<Preview> {
+    let (program, source) = program(id)?;
+    let preview = preview_program(live, &program, source);
+    info!(
+        "Vista previa de la macro {}: {} eventos en {} µs",
+        id,
+        preview.timeline.len(),
+        preview.duration_us
+    );
+    Ok(preview)
+}
+
+fn preview_program(live: &Executor, program: &[SequenceStep], source: RunSource) -> Preview {
+    let output = Arc::new(RecordingOutput::starting_at(*CURSOR.lock()));
+    // Las esperas y el límite de los scripts corren en el mismo tiempo virtual
+    let executor = live.detached(output.clone()).with_clock(output.clock());
+
+    let result = executor.run_sequence(program, source);
+    let (timeline, duration) = output.finish();
+    Preview {
+        timeline,
+        duration_us: duration.as_micros() as u64,
+        error: result.err().map(|e| format!("{e:#}")),
+        truncated: output.truncated(),
+    }
+}
+
+/// Ejecuta la macro en tiempo real en segundo plano, emitiendo a la UI los
+/// eventos en lugar de enviarlos al sistema. Devuelve el id de la ejecución,
+/// que se cancela como cualquier otra.
+pub fn simulate_macro(live: &Executor, id: u64) -> Result<u64> {
+    let (program, source) = program(id)?;
+    spawn_simulation(live, program, source).map(|(run_id, _)| run_id)
+}
+
+fn spawn_simulation(
+    live: &Executor,
+    program: Vec<SequenceStep>,
+    mut source: RunSource,
+) -> Result<(u64, JoinHandle<()>)> {
+    let output: Arc<dyn OutputBackend> = Arc::new(SimulatedOutput::starting_at(*CURSOR.lock()));
+    let executor = live.detached(output);
+    let run_id = executor.register_run();
+    source.run_id = Some(run_id);
+
+    let handle = thread::Builder::new()
+        .name("macro-simulation".into())
+        .spawn(move || {
+            if let Err(e) = executor.run_sequence(&program, source) {
+                error!("Error al simular la ejecución {}: {}", run_id, e);
+            }
+        });
+    match handle {
+        Ok(handle) => Ok((run_id, handle)),
+        Err(e) => {
+            live.set_status(
+                run_id,
+                RunStatus::Finished {
+                    outcome: RunOutcome::Failed,
+                    error: Some(e.to_string()),
+                },
+            );
+            Err(e).context("No se pudo iniciar la simulación")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::sequence_step::Timing,
+        engine::output::{MAX_PREVIEW_DURATION, MAX_PREVIEW_EVENTS},
+    };
+
+    fn repeat(body: Vec<SequenceStep>) -> Vec<SequenceStep> {
+        vec![SequenceStep::Repeat {
+            id: 1,
+            count: u32::MAX,
+            body,
+        }]
+    }
+
+    #[test]
+    fn endless_delays_stop_at_the_time_limit() {
+        let program = repeat(vec![SequenceStep::Delay {
+            id: 2,
+            ms: Timing::Fixed(1000),
+        }]);
+        let preview = preview_program(
+            &Executor::with_seed(Some(1)),
+            &program,
+            RunSource::default(),
+        );
+
+        assert!(preview.truncated);
+        assert!(preview.error.is_some());
+        assert_eq!(preview.duration_us, MAX_PREVIEW_DURATION.as_micros() as u64);
+    }
+
+    #[test]
+    fn endless_taps_stop_at_the_event_limit() {
+        let program = repeat(vec![SequenceStep::Tap {
+            id: 2,
+            key: "A".into(),
+            hold_ms: Timing::Fixed(0),
+            hold_frames: None,
+        }]);
+        let preview = preview_program(
+            &Executor::with_seed(Some(1)),
+            &program,
+            RunSource::default(),
+        );
+
+        assert!(preview.truncated);
+        assert_eq!(preview.timeline.len(), MAX_PREVIEW_EVENTS);
+    }
+
+    fn long_delay() -> Vec<SequenceStep> {
+        vec![SequenceStep::Delay {
+            id: 1,
+            ms: Timing::Fixed(60_000),
+        }]
+    }
+
+    fn cancelled() -> Option<RunStatus> {
+        Some(RunStatus::Finished {
+            outcome: RunOutcome::Cancelled,
+            error: None,
+        })
+    }
+
+    #[test]
+    fn simulations_take_ids_from_the_live_executor() {
+        let live = Executor::with_seed(Some(1));
+        let before = live.register_run();
+        let (run_id, handle) = spawn_simulation(&live, long_delay(), RunSource::default()).unwrap();
+
+        assert_eq!(run_id, before + 1);
+        assert_eq!(live.register_run(), run_id + 1);
+        assert!(live.cancel_run(run_id));
+        handle.join().unwrap();
+        assert_eq!(live.run_status(run_id), cancelled());
+    }
+
+    #[test]
+    fn shutdown_stops_simulations() {
+        let live = Executor::with_seed(Some(1));
+        let (run_id, handle) = spawn_simulation(&live, long_delay(), RunSource::default()).unwrap();
+
+        live.shutdown();
+        handle.join().unwrap();
+        assert_eq!(live.run_status(run_id), cancelled());
+    }
+
+    #[test]
+    fn finished_previews_are_not_truncated() {
+        let program = vec![SequenceStep::Delay {
+            id: 1,
+            ms: Timing::Fixed(5),
+        }];
+        let preview = preview_program(
+            &Executor::with_seed(Some(1)),
+            &program,
+            RunSource::default(),
+        );
+
+        assert!(!preview.truncated);
+        assert_eq!(preview.error, None);
+        assert_eq!(preview.duration_us, 5_000);
+    }
+}
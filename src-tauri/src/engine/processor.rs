@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use anyhow::{Context, Result};
 use crossbeam_channel::Receiver;
@@ -9,8 +9,8 @@ use tracing::{error, info, warn};
 use crate::{
     config::handler::{get_config, save_config},
     domain::enums::ListenableChannel,
-    engine::handler::QueueHandler,
-    input::handler::{HotkeyEvent, KeyboardHandler},
+    engine::{handler::QueueHandler, queue::RunJob},
+    input::handler::{HotkeyEvent, KeyboardHandler, SharedState},
 };
 
 #[derive(Debug)]
@@ -21,11 +21,15 @@ pub struct EventProcessor {
 }
 
 impl EventProcessor {
-    pub fn new(queue_handler: QueueHandler, app_handle: AppHandle) -> Result<Arc<Self>> {
+    pub fn new(
+        queue_handler: QueueHandler,
+        key_state: SharedState,
+        app_handle: AppHandle,
+    ) -> Result<Arc<Self>> {
         info!("Iniciando procesador de eventos");
         Ok(Arc::new(Self {
             queue_handler,
-            keyboard_handler: Mutex::new(KeyboardHandler::new()),
+            keyboard_handler: Mutex::new(KeyboardHandler::new(key_state)),
             app_handle,
         }))
     }
@@ -63,8 +67,8 @@ impl EventProcessor {
     fn handle_event(&self, ev: HotkeyEvent) -> Result<()> {
         info!("Evento recibido: {:?}", ev);
         match ev {
-            HotkeyEvent::ComboTriggered(id) => {
-                self.handle_macro_trigger(&id)?;
+            HotkeyEvent::ComboTriggered(id, at_us) => {
+                self.handle_macro_trigger(&id, at_us)?;
             }
             HotkeyEvent::ProfileSwitch(id) => {
                 self.handle_profile_switch(&id)?;
@@ -74,12 +78,16 @@ impl EventProcessor {
         Ok(())
     }
 
-    fn handle_macro_trigger(&self, id: &u64) -> Result<()> {
+    fn handle_macro_trigger(&self, id: &u64, at_us: u64) -> Result<()> {
         info!("Buscando macro con ID: {}", id);
         let cfg = get_config();
         if let Some(macro_def) = cfg.find_macro(id) {
             info!("Macro encontrada: {}", macro_def.name); // ← Agregar este log
-            self.queue_handler.push(macro_def.clone())?;
+            self.queue_handler.push(
+                RunJob::Macro(macro_def.clone()),
+                Duration::ZERO,
+                Some(at_us),
+            )?;
         } else {
             warn!("No se encontró macro con ID: {}", id); // ← Agregar este log
         }
@@ -90,7 +98,7 @@ impl EventProcessor {
         info!("Cambiando perfil a: {}", id);
         let cfg = get_config();
 
-        save_config(|config| {
+        save_config("Cambiar perfil activo", |config| {
             if let Some(profile) = cfg.find_profile(id).cloned() {
                 config.selected_profile_id = Some(profile.id);
                 info!("Perfil cambiado a: {}", id);
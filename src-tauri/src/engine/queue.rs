@@ -1,16 +1,37 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::{
     config::{handler::get_config, model::StoredMacro},
-    engine::executor::Executor,
+    domain::sequence_step::SequenceStep,
+    engine::executor::{Executor, RunSource},
+    keys,
 };
 use anyhow::Result;
 use threadpool::ThreadPool;
 use tracing::{error, info};
 
+/// Qué ejecuta un trabajo de la cola.
+#[derive(Debug)]
+pub enum RunJob {
+    Macro(StoredMacro),
+    /// Pasos sueltos, p. ej. los que se prueban desde la UI.
+    Sequence(Vec<SequenceStep>),
+}
+
+#[derive(Debug)]
+pub struct QueuedRun {
+    /// Reservado con `Executor::register_run` al encolar.
+    pub run_id: u64,
+    pub job: RunJob,
+    pub delay_before: Duration,
+    /// Cuándo se pulsó el disparador, en microsegundos del reloj del
+    /// ejecutor, para medir la latencia.
+    pub triggered_at_us: Option<u64>,
+}
+
 #[derive(Debug)]
 pub enum QueueCommand {
-    Push(StoredMacro),
+    Push(QueuedRun),
 }
 
 pub struct MacroQueue {
@@ -42,20 +63,40 @@ impl MacroQueue {
             crossbeam_channel::select! {
                 recv(self.rx) -> msg => {
                     match msg {
-                        Ok(QueueCommand::Push(m)) => {
-                            info!("Macro recibida en cola: {}", m.name);
+                        Ok(QueueCommand::Push(run)) => {
+                            info!("Ejecución {} recibida en cola", run.run_id);
                             let config = get_config();
                             let executor = self.executor.clone();
 
                             self.pool.execute(move || {
-                                let sequence = m
-                                    .sequence_step_ids
-                                    .iter()
-                                    .filter_map(|s| config.steps.get(s).cloned())
-                                    .collect::<Vec<_>>();
+                                let source = RunSource {
+                                    run_id: Some(run.run_id),
+                                    delay_before: run.delay_before,
+                                    triggered_at_us: run.triggered_at_us,
+                                    ..Default::default()
+                                };
+                                let (sequence, source, name) = match run.job {
+                                    RunJob::Macro(m) => {
+                                        let source = RunSource {
+                                            macro_id: Some(m.id),
+                                            profile_id: config.profile_of_macro(m.id),
+                                            trigger: Some(keys::str_to_input(&m.trigger.key)),
+                                            ..source
+                                        };
+                                        (config.program_of(&m), source, m.name)
+                                    }
+                                    // Sin macro, las variables son las del perfil activo
+                                    RunJob::Sequence(steps) => {
+                                        let source = RunSource {
+                                            profile_id: config.selected_profile_id,
+                                            ..source
+                                        };
+                                        (steps, source, "secuencia suelta".to_string())
+                                    }
+                                };
 
-                                if let Err(e) = executor.run_sequence(&sequence) {
-                                    error!("Error al ejecutar macro {}: {}", m.name, e)
+                                if let Err(e) = executor.run_sequence(&sequence, source) {
+                                    error!("Error al ejecutar {}: {}", name, e)
                                 }
                             });
                         },
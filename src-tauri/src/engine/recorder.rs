@@ -0,0 +1,398 @@
+use std::{
+    cell::RefCell, collections::VecDeque, fmt::Write as _, path::Path, sync::Arc, time::Duration,
+};
+
+use anyhow::Result;
+use parking_lot::Mutex;
+
+use crate::{
+    config::handler::write_atomic,
+    domain::run::{LatencyStats, Percentiles, RunOutcome, RunReport, StepTiming},
+    engine::clock::Clock,
+};
+
+/// Ejecuciones que se conservan; al llenarse se descarta la más antigua.
+const HISTORY_CAPACITY: usize = 200;
+
+static HISTORY: Mutex<VecDeque<RunReport>> = Mutex::new(VecDeque::new());
+
+/// Ejecución en curso en este hilo. Cada macro corre entera en un hilo del
+/// pool, así que los pasos anidados, los scripts y `send_event` la encuentran
+/// sin tener que pasarla de mano en mano.
+struct Recording {
+    report: RunReport,
+    /// El del ejecutor, para medir con el mismo tiempo con el que espera.
+    clock: Arc<dyn Clock>,
+    triggered_at_us: Option<u64>,
+    /// Inicio previsto del siguiente paso, relativo al inicio.
+    planned_us: u64,
+    /// Pasos abiertos, por si uno compuesto envuelve a otros.
+    open: Vec<usize>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Recording>> = const { RefCell::new(None) };
+}
+
+fn with_current(f: impl FnOnce(&mut Recording)) {
+    CURRENT.with_borrow_mut(|current| {
+        if let Some(rec) = current.as_mut() {
+            f(rec);
+        }
+    });
+}
+
+pub fn begin(
+    run_id: u64,
+    macro_id: Option<u64>,
+    triggered_at_us: Option<u64>,
+    clock: Arc<dyn Clock>,
+) {
+    let report = RunReport {
+        run_id,
+        macro_id,
+        started_at_us: clock.now_us(),
+        duration_us: 0,
+        outcome: RunOutcome::Completed,
+        trigger_latency_us: None,
+        max_jitter_us: 0,
+        steps: Vec::new(),
+    };
+    CURRENT.set(Some(Recording {
+        report,
+        clock,
+        triggered_at_us,
+        planned_us: 0,
+        open: Vec::new(),
+    }));
+}
+
+#[inline]
+fn elapsed(rec: &Recording) -> u64 {
+    rec.clock.now_us().saturating_sub(rec.report.started_at_us)
+}
+
+pub fn step_started(step_id: u64, depth: usize) {
+    with_current(|rec| {
+        let actual_us = elapsed(rec);
+        rec.open.push(rec.report.steps.len());
+        rec.report.steps.push(StepTiming {
+            step_id,
+            depth,
+            intended_us: rec.planned_us,
+            actual_us,
+            duration_us: 0,
+        });
+    });
+}
+
+pub fn step_finished() {
+    with_current(|rec| {
+        let now = elapsed(rec);
+        if let Some(step) = rec.open.pop().and_then(|i| rec.report.steps.get_mut(i)) {
+            step.duration_us = now.saturating_sub(step.actual_us);
+        }
+    });
+}
+
+/// Suma una espera deliberada al plan de la ejecución.
+pub fn plan(duration: Duration) {
+    with_current(|rec| rec.planned_us += duration.as_micros() as u64);
+}
+
+/// Tras una espera de duración imprevisible el plan continúa desde ahora.
+pub fn resync() {
+    with_current(|rec| rec.planned_us = elapsed(rec));
+}
+
+/// Anota la primera salida hacia el sistema.
+pub fn output() {
+    with_current(|rec| {
+        if rec.report.trigger_latency_us.is_none() {
+            let now_us = rec.clock.now_us();
+            rec.report.trigger_latency_us = rec
+                .triggered_at_us
+                .map(|triggered| now_us.saturating_sub(triggered));
+        }
+    });
+}
+
+pub fn finish(outcome: RunOutcome) {
+    if let Some(report) = take_report(outcome) {
+        push(&mut HISTORY.lock(), report);
+    }
+}
+
+/// Cierra la ejecución en curso en este hilo y devuelve su informe.
+fn take_report(outcome: RunOutcome) -> Option<RunReport> {
+    let mut rec = CURRENT.take()?;
+    let report = &mut rec.report;
+    report.duration_us = rec.clock.now_us().saturating_sub(report.started_at_us);
+    report.outcome = outcome;
+    report.max_jitter_us = report
+        .steps
+        .iter()
+        .map(StepTiming::jitter_us)
+        .max()
+        .unwrap_or(0);
+    Some(rec.report)
+}
+
+fn push(history: &mut VecDeque<RunReport>, report: RunReport) {
+    if history.len() == HISTORY_CAPACITY {
+        history.pop_front();
+    }
+    history.push_back(report);
+}
+
+/// Ejecuciones recientes, de la más nueva a la más antigua.
+pub fn history() -> Vec<RunReport> {
+    HISTORY.lock().iter().rev().cloned().collect()
+}
+
+pub fn stats() -> LatencyStats {
+    stats_of(&HISTORY.lock())
+}
+
+fn stats_of(history: &VecDeque<RunReport>) -> LatencyStats {
+    let latencies = history
+        .iter()
+        .filter_map(|r| r.trigger_latency_us)
+        .collect();
+    let jitter = history
+        .iter()
+        .flat_map(|r| r.steps.iter().map(StepTiming::jitter_us))
+        .collect();
+
+    LatencyStats {
+        runs: history.len(),
+        trigger_latency: percentiles(latencies),
+        step_jitter: percentiles(jitter),
+    }
+}
+
+/// Percentiles por rango más cercano; `None` sin muestras.
+fn percentiles(mut samples: Vec<u64>) -> Option<Percentiles> {
+    samples.sort_unstable();
+    let max = *samples.last()?;
+    let at = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+    Some(Percentiles {
+        p50: at(50),
+        p95: at(95),
+        p99: at(99),
+        max,
+    })
+}
+
+/// Exporta el historial como CSV, una fila por paso.
+pub fn export_csv(path: &Path) -> Result<()> {
+    write_atomic(path, to_csv(&history())?.as_bytes())
+}
+
+fn to_csv(runs: &[RunReport]) -> Result<String> {
+    let mut csv = String::from(
+        "run_id,macro_id,started_at_us,outcome,trigger_latency_us,step_id,depth,intended_us,actual_us,jitter_us,duration_us\n",
+    );
+    for run in runs {
+        let macro_id = run.macro_id.map(|id| id.to_string()).unwrap_or_default();
+        let latency = run
+            .trigger_latency_us
+            .map(|us| us.to_string())
+            .unwrap_or_default();
+        let outcome = serde_json::to_value(run.outcome)?;
+        let outcome = outcome.as_str().unwrap_or_default();
+
+        for step in &run.steps {
+            writeln!(
+                csv,
+                "{},{macro_id},{},{outcome},{latency},{},{},{},{},{},{}",
+                run.run_id,
+                run.started_at_us,
+                step.step_id,
+                step.depth,
+                step.intended_us,
+                step.actual_us,
+                step.jitter_us(),
+                step.duration_us,
+            )?;
+        }
+    }
+    Ok(csv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::clock::ManualClock;
+
+    const MS: u64 = 1000;
+
+    fn report(run_id: u64, latency_us: Option<u64>, jitters_us: &[u64]) -> RunReport {
+        RunReport {
+            run_id,
+            macro_id: Some(7),
+            started_at_us: 1_000_000,
+            duration_us: 0,
+            outcome: RunOutcome::Completed,
+            trigger_latency_us: latency_us,
+            max_jitter_us: 0,
+            steps: jitters_us
+                .iter()
+                .enumerate()
+                .map(|(i, jitter)| StepTiming {
+                    step_id: i as u64 + 1,
+                    depth: 0,
+                    intended_us: 0,
+                    actual_us: *jitter,
+                    duration_us: 0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn records_steps_against_the_plan() {
+        let clock = Arc::new(ManualClock::default());
+        clock.advance(Duration::from_secs(5));
+        begin(1, Some(7), Some(clock.now_us() - 2 * MS), clock.clone());
+
+        // Pulsa en el instante 0 y espera 100 ms, pero el reloj avanza 103
+        step_started(10, 0);
+        output();
+        plan(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(103));
+        step_finished();
+
+        // Paso compuesto que envuelve a otro
+        step_started(11, 0);
+        step_started(12, 1);
+        clock.advance(Duration::from_millis(5));
+        step_finished();
+        step_finished();
+        output();
+
+        let report = take_report(RunOutcome::Completed).unwrap();
+        assert!(take_report(RunOutcome::Completed).is_none());
+
+        assert_eq!(report.trigger_latency_us, Some(2 * MS));
+        assert_eq!(report.duration_us, 108 * MS);
+        let timings = report
+            .steps
+            .iter()
+            .map(|s| {
+                (
+                    s.step_id,
+                    s.depth,
+                    s.intended_us,
+                    s.actual_us,
+                    s.duration_us,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            timings,
+            vec![
+                (10, 0, 0, 0, 103 * MS),
+                (11, 0, 100 * MS, 103 * MS, 5 * MS),
+                (12, 1, 100 * MS, 103 * MS, 5 * MS),
+            ]
+        );
+        assert_eq!(report.max_jitter_us, 3 * MS);
+    }
+
+    #[test]
+    fn resync_moves_the_plan_to_now() {
+        let clock = Arc::new(ManualClock::default());
+        begin(1, None, None, clock.clone());
+        clock.advance(Duration::from_millis(40));
+        resync();
+        step_started(10, 0);
+
+        let report = take_report(RunOutcome::Cancelled).unwrap();
+        assert_eq!(report.outcome, RunOutcome::Cancelled);
+        assert_eq!(report.trigger_latency_us, None);
+        assert_eq!(report.steps[0].jitter_us(), 0);
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        assert_eq!(percentiles(Vec::new()), None);
+        assert_eq!(
+            percentiles(vec![42]),
+            Some(Percentiles {
+                p50: 42,
+                p95: 42,
+                p99: 42,
+                max: 42,
+            })
+        );
+        // 1..=100 desordenado: cada percentil es su propio rango
+        let samples = (1..=100).rev().collect();
+        assert_eq!(
+            percentiles(samples),
+            Some(Percentiles {
+                p50: 50,
+                p95: 95,
+                p99: 99,
+                max: 100,
+            })
+        );
+        assert_eq!(
+            percentiles(vec![10, 20, 30, 40]),
+            Some(Percentiles {
+                p50: 20,
+                p95: 40,
+                p99: 40,
+                max: 40,
+            })
+        );
+    }
+
+    #[test]
+    fn stats_cover_latencies_and_every_step() {
+        let history = VecDeque::from([
+            report(1, Some(300), &[10, 20]),
+            report(2, None, &[30]),
+            report(3, Some(100), &[]),
+        ]);
+
+        let stats = stats_of(&history);
+        assert_eq!(stats.runs, 3);
+        assert_eq!(stats.trigger_latency.unwrap().p50, 100);
+        assert_eq!(stats.trigger_latency.unwrap().max, 300);
+        assert_eq!(stats.step_jitter.unwrap().p50, 20);
+        assert_eq!(stats.step_jitter.unwrap().max, 30);
+    }
+
+    #[test]
+    fn history_drops_the_oldest_beyond_capacity() {
+        let mut history = VecDeque::new();
+        for run_id in 1..=HISTORY_CAPACITY as u64 + 5 {
+            push(&mut history, report(run_id, None, &[]));
+        }
+
+        assert_eq!(history.len(), HISTORY_CAPACITY);
+        assert_eq!(history.front().unwrap().run_id, 6);
+        assert_eq!(history.back().unwrap().run_id, HISTORY_CAPACITY as u64 + 5);
+    }
+
+    #[test]
+    fn csv_has_one_row_per_step() {
+        let mut failed = report(2, None, &[]);
+        failed.outcome = RunOutcome::Failed;
+        failed.macro_id = None;
+        failed.steps = report(0, None, &[5]).steps;
+
+        let csv = to_csv(&[report(1, Some(250), &[0, 40]), failed]).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                "run_id,macro_id,started_at_us,outcome,trigger_latency_us,step_id,depth,intended_us,actual_us,jitter_us,duration_us",
+                "1,7,1000000,completed,250,1,0,0,0,0,0",
+                "1,7,1000000,completed,250,2,0,0,40,40,0",
+                "2,,1000000,failed,,1,0,0,5,5,0",
+            ]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender, TrySendError};
+use tauri::{AppHandle, Emitter};
+use tracing::{error, info, warn};
+
+use crate::domain::{
+    enums::ListenableChannel,
+    run::{RunEvent, RunEventBatch},
+};
+
+/// Los eventos se agrupan y se emiten como mucho una vez por intervalo.
+const EMIT_INTERVAL: Duration = Duration::from_millis(50);
+/// Eventos pendientes antes de empezar a descartar.
+const CAPACITY: usize = 4096;
+
+static RUN_EVENTS: OnceLock<Sender<RunEvent>> = OnceLock::new();
+/// Para avisar una sola vez de que se están descartando eventos.
+static DROPPING: AtomicBool = AtomicBool::new(false);
+
+/// Arranca el hilo que emite los eventos de ejecución a la UI.
+pub fn init_run_events(handle: AppHandle) {
+    let (tx, rx) = bounded(CAPACITY);
+    if RUN_EVENTS.set(tx).is_err() {
+        warn!("Los eventos de ejecución ya estaban iniciados");
+        return;
+    }
+
+    thread::Builder::new()
+        .name("run-events".into())
+        .spawn(move || emit_loop(&handle, &rx))
+        .map_err(|e| error!("No se pudo iniciar el emisor de eventos: {e}"))
+        .ok();
+}
+
+/// Encola el evento sin bloquear nunca: si la cola está llena se descarta,
+/// porque retrasar la salida de teclas sería peor que perder progreso en la UI.
+#[inline]
+pub fn publish(event: RunEvent) {
+    let Some(tx) = RUN_EVENTS.get() else {
+        return;
+    };
+    match tx.try_send(event) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) => {
+            if !DROPPING.swap(true, Ordering::Relaxed) {
+                warn!("Cola de eventos de ejecución llena, se descartan eventos");
+            }
+        }
+        Err(TrySendError::Disconnected(_)) => {}
+    }
+}
+
+#[inline]
+pub fn now_us() -> u64 {
+    to_us(SystemTime::now())
+}
+
+/// Microsegundos desde la época Unix.
+#[inline]
+pub fn to_us(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or_default()
+}
+
+fn emit_loop(handle: &AppHandle, rx: &Receiver<RunEvent>) {
+    let channel = ListenableChannel::SequenceStep.to_string();
+    // Espera al primer evento y recoge lo que llegue durante el intervalo
+    while let Ok(first) = rx.recv() {
+        let mut batch = RunEventBatch(vec![first]);
+        let deadline = Instant::now() + EMIT_INTERVAL;
+        let disconnected = loop {
+            match rx.recv_deadline(deadline) {
+                Ok(event) => batch.0.push(event),
+                Err(RecvTimeoutError::Timeout) => break false,
+                Err(RecvTimeoutError::Disconnected) => break true,
+            }
+        };
+
+        emit_batch(handle, &channel, &batch);
+        DROPPING.store(false, Ordering::Relaxed);
+        if disconnected {
+            break;
+        }
+    }
+    info!("Emisor de eventos de ejecución detenido");
+}
+
+fn emit_batch(handle: &AppHandle, channel: &str, batch: &RunEventBatch) {
+    if let Err(e) = handle.emit(channel, batch) {
+        error!("Error al emitir evento: {e}");
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use ahash::AHashMap;
+use anyhow::{bail, Context, Result};
+use parking_lot::Mutex;
+use tracing::{info, warn};
+
+use crate::{
+    config::{
+        handler::{write_atomic, CONFIG},
+        path::sibling_path,
+    },
+    domain::{sequence_step::Side, variables::VarValue},
+};
+
+/// Junto a la configuración: `<nombre>.variables.json`.
+const VARIABLES_FILE: &str = "variables.json";
+
+/// Variables de un perfil, ordenadas para la UI y el archivo.
+pub type Variables = BTreeMap<String, VarValue>;
+
+/// Estado que los pasos leen y cambian mientras se ejecutan las macros. No
+/// forma parte de la configuración; las variables de los perfiles con
+/// `persist_variables` se guardan aparte al terminar cada ejecución.
+#[derive(Debug, Default)]
+pub struct RuntimeState {
+    facing: Mutex<Side>,
+    /// Variables por perfil; `None` para lo que se ejecuta fuera de un perfil.
+    variables: Mutex<AHashMap<Option<u64>, Variables>>,
+    dirty: AtomicBool,
+    /// Copia para la vista previa o la simulación; nunca se guarda.
+    detached: bool,
+}
+
+#[inline]
+fn variables_path() -> PathBuf {
+    sibling_path(VARIABLES_FILE)
+}
+
+impl RuntimeState {
+    /// Recupera las variables guardadas; si el archivo falta o está dañado se empieza de cero.
+    pub fn load() -> Self {
+        let state = Self::default();
+        let path = variables_path();
+        let saved = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice::<BTreeMap<u64, Variables>>(&bytes)
+                .inspect_err(|e| warn!("Variables guardadas ilegibles, se ignoran: {e}"))
+                .unwrap_or_default(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                warn!("No se pudo leer {}: {e}", path.display());
+                BTreeMap::new()
+            }
+        };
+
+        if !saved.is_empty() {
+            info!("Variables recuperadas de {} perfiles", saved.len());
+        }
+        *state.variables.lock() = saved.into_iter().map(|(id, v)| (Some(id), v)).collect();
+        state
+    }
+
+    /// Copia del estado actual que se puede modificar sin afectar al original.
+    pub fn detached_copy(&self) -> Self {
+        Self {
+            facing: Mutex::new(self.facing()),
+            variables: Mutex::new(self.variables.lock().clone()),
+            dirty: AtomicBool::new(false),
+            detached: true,
+        }
+    }
+
+    #[inline]
+    pub fn facing(&self) -> Side {
+        *self.facing.lock()
+    }
+
+    #[inline]
+    pub fn set_facing(&self, side: Side) {
+        *self.facing.lock() = side;
+    }
+
+    #[inline]
+    pub fn variable(&self, profile_id: Option<u64>, name: &str) -> Option<VarValue> {
+        self.variables
+            .lock()
+            .get(&profile_id)
+            .and_then(|vars| vars.get(name))
+            .copied()
+    }
+
+    #[inline]
+    pub fn set_variable(&self, profile_id: Option<u64>, name: &str, value: VarValue) {
+        self.variables
+            .lock()
+            .entry(profile_id)
+            .or_default()
+            .insert(name.to_string(), value);
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Suma `by` a una variable entera; sin valor previo parte de 0. Con
+    /// `wrap`, el resultado vuelve a empezar en 0 al llegar a `wrap`.
+    pub fn increment(
+        &self,
+        profile_id: Option<u64>,
+        name: &str,
+        by: i64,
+        wrap: Option<i64>,
+    ) -> Result<i64> {
+        let mut variables = self.variables.lock();
+        let slot = variables
+            .entry(profile_id)
+            .or_default()
+            .entry(name.to_string())
+            .or_insert(VarValue::Int(0));
+
+        let VarValue::Int(current) = *slot else {
+            bail!("La variable {name} no es un entero");
+        };
+        let mut value = current.wrapping_add(by);
+        if let Some(wrap) = wrap.filter(|w| *w > 0) {
+            value = value.rem_euclid(wrap);
+        }
+        *slot = VarValue::Int(value);
+        self.dirty.store(true, Ordering::SeqCst);
+        Ok(value)
+    }
+
+    pub fn variables(&self, profile_id: Option<u64>) -> Variables {
+        self.variables
+            .lock()
+            .get(&profile_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Borra las variables del perfil o, sin perfil, todas.
+    pub fn reset_variables(&self, profile_id: Option<u64>) {
+        let mut variables = self.variables.lock();
+        match profile_id {
+            Some(id) => {
+                variables.remove(&Some(id));
+            }
+            None => variables.clear(),
+        }
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Guarda las variables de los perfiles que las conservan, solo si cambiaron.
+    pub fn flush(&self) -> Result<()> {
+        if self.detached || !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let persisted = {
+            let cfg = CONFIG.read();
+            let variables = self.variables.lock();
+            variables
+                .iter()
+                .filter_map(|(id, vars)| id.map(|id| (id, vars)))
+                .filter(|(id, _)| cfg.profiles.get(id).is_some_and(|p| p.persist_variables))
+                .map(|(id, vars)| (id, vars.clone()))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let json = serde_json::to_vec_pretty(&persisted)
+            .context("No se pudieron serializar las variables")?;
+        write_atomic(&variables_path(), &json)
+    }
+}
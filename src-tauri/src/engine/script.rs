@@ -0,0 +1,206 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use crossbeam_channel::{Receiver, TryRecvError};
+use rdev::EventType;
+use rhai::{module_resolvers::DummyModuleResolver, Dynamic, Engine, EvalAltResult, Position};
+use tracing::{debug, info};
+
+use crate::{
+    domain::{sequence_step::Side, variables::VarValue},
+    engine::{
+        clock::Clock, executor::Aborted, output::OutputBackend, recorder, runtime::RuntimeState,
+    },
+    input::state::KEY_STATE,
+    keys::{self, InputKey},
+};
+
+/// Tiempo máximo de un script, contando sus esperas.
+pub const MAX_SCRIPT_DURATION: Duration = Duration::from_secs(60);
+
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_STRING_SIZE: usize = 4096;
+const MAX_ARRAY_SIZE: usize = 1024;
+const MAX_MAP_SIZE: usize = 256;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Lo que un script necesita del ejecutor. Se comparte con `Arc` porque las
+/// funciones registradas en el motor deben ser `'static`.
+#[derive(Debug, Clone)]
+pub struct ScriptHost {
+    /// Se desconecta al cancelar la ejecución o apagar el ejecutor.
+    pub cancel_rx: Receiver<()>,
+    /// El del ejecutor; mide también [`MAX_SCRIPT_DURATION`].
+    pub clock: Arc<dyn Clock>,
+    pub runtime: Arc<RuntimeState>,
+    pub output: Arc<dyn OutputBackend>,
+    /// Perfil de las variables que leen `get` y `set`.
+    pub profile_id: Option<u64>,
+}
+
+/// Motor sin acceso a archivos ni `eval`, con límites de tamaño y anidamiento.
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_ARRAY_SIZE)
+        .set_max_map_size(MAX_MAP_SIZE)
+        .disable_symbol("eval")
+        .on_print(|s| info!("[script] {}", s))
+        .on_debug(|s, _, pos| debug!("[script] {} {:?}", s, pos));
+    engine
+}
+
+/// Compila el script sin ejecutarlo; el error incluye la posición.
+pub fn check(source: &str) -> Result<(), String> {
+    sandboxed_engine()
+        .compile(source)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Ejecuta el script hasta que termine, se agote [`MAX_SCRIPT_DURATION`] o
+/// se cancele la ejecución, que cuenta como [`Aborted`].
+pub fn run(host: &ScriptHost, source: &str) -> Result<()> {
+    let deadline_us = host.clock.now_us() + MAX_SCRIPT_DURATION.as_micros() as u64;
+    let mut engine = sandboxed_engine();
+    register_api(&mut engine, host, deadline_us);
+
+    // Un bucle sin esperas no adelanta un reloj virtual; el real lo acota igual
+    let real_deadline = Instant::now() + MAX_SCRIPT_DURATION;
+    let (cancel_rx, clock) = (host.cancel_rx.clone(), host.clock.clone());
+    engine.on_progress(move |_| {
+        let stop = cancelled(&cancel_rx)
+            || clock.now_us() >= deadline_us
+            || Instant::now() >= real_deadline;
+        stop.then_some(Dynamic::UNIT)
+    });
+
+    let ast = engine
+        .compile(source)
+        .map_err(|e| anyhow!("El script no compila: {e}"))?;
+
+    match engine.run_ast(&ast) {
+        Ok(()) => Ok(()),
+        Err(e) if matches!(*e, EvalAltResult::ErrorTerminated(..)) => {
+            if cancelled(&host.cancel_rx) {
+                return Err(Aborted.into());
+            }
+            bail!(
+                "El script superó el límite de {} s",
+                MAX_SCRIPT_DURATION.as_secs()
+            )
+        }
+        Err(e) => bail!("Error en el script: {e}"),
+    }
+}
+
+#[inline]
+fn cancelled(cancel_rx: &Receiver<()>) -> bool {
+    matches!(cancel_rx.try_recv(), Err(TryRecvError::Disconnected))
+}
+
+fn terminated() -> Box<EvalAltResult> {
+    EvalAltResult::ErrorTerminated(Dynamic::UNIT, Position::NONE).into()
+}
+
+fn send_input(host: &ScriptHost, key: &str, down: bool) -> ScriptResult<()> {
+    let event = match (keys::str_to_input(key), down) {
+        (InputKey::Key(k), true) => EventType::KeyPress(k),
+        (InputKey::Key(k), false) => EventType::KeyRelease(k),
+        (InputKey::Button(b), true) => EventType::ButtonPress(b),
+        (InputKey::Button(b), false) => EventType::ButtonRelease(b),
+    };
+    host.output.send(event).map_err(|e| e.to_string().into())
+}
+
+/// Espera sin pasarse del límite del script; la cancelación la corta.
+fn wait_until(host: &ScriptHost, ms: i64, deadline_us: u64) -> ScriptResult<()> {
+    let wanted = Duration::from_millis(ms.max(0) as u64);
+    let remaining = Duration::from_micros(deadline_us.saturating_sub(host.clock.now_us()));
+    recorder::plan(wanted);
+
+    let waited = host
+        .output
+        .wait(&*host.clock, wanted.min(remaining), &host.cancel_rx)
+        .map_err(|e| -> Box<EvalAltResult> { e.to_string().into() })?;
+    if !waited || wanted > remaining {
+        return Err(terminated());
+    }
+    Ok(())
+}
+
+fn register_api(engine: &mut Engine, host: &ScriptHost, deadline_us: u64) {
+    let h = host.clone();
+    engine.register_fn("press", move |key: &str| send_input(&h, key, true));
+    let h = host.clone();
+    engine.register_fn("release", move |key: &str| send_input(&h, key, false));
+    let h = host.clone();
+    engine.register_fn("tap", move |key: &str| {
+        send_input(&h, key, true)?;
+        send_input(&h, key, false)
+    });
+
+    let h = host.clone();
+    engine.register_fn("tap", move |key: &str, ms: i64| {
+        send_input(&h, key, true)?;
+        // Se suelta aunque la espera se corte para no dejar la tecla pegada
+        let waited = wait_until(&h, ms, deadline_us);
+        send_input(&h, key, false)?;
+        waited
+    });
+
+    let h = host.clone();
+    engine.register_fn("wait", move |ms: i64| wait_until(&h, ms, deadline_us));
+
+    engine.register_fn("is_pressed", |key: &str| {
+        let state = KEY_STATE.lock();
+        keys::str_to_held_inputs(key)
+            .into_iter()
+            .any(|k| state.is_pressed(k))
+    });
+
+    let runtime = host.runtime.clone();
+    engine.register_fn("facing", move || match runtime.facing() {
+        Side::Left => "left",
+        Side::Right => "right",
+    });
+
+    let runtime = host.runtime.clone();
+    engine.register_fn("set_facing", move |side: &str| -> ScriptResult<()> {
+        let side = match side {
+            "left" => Side::Left,
+            "right" => Side::Right,
+            _ => return Err(format!("Lado desconocido: {side}").into()),
+        };
+        runtime.set_facing(side);
+        Ok(())
+    });
+
+    let (runtime, profile_id) = (host.runtime.clone(), host.profile_id);
+    engine.register_fn("get", move |name: &str| {
+        match runtime.variable(profile_id, name) {
+            Some(VarValue::Bool(b)) => Dynamic::from(b),
+            Some(VarValue::Int(i)) => Dynamic::from(i),
+            None => Dynamic::UNIT,
+        }
+    });
+
+    let runtime = host.runtime.clone();
+    engine.register_fn("set", move |name: &str, value: i64| {
+        runtime.set_variable(profile_id, name, VarValue::Int(value));
+    });
+
+    let runtime = host.runtime.clone();
+    engine.register_fn("set", move |name: &str, value: bool| {
+        runtime.set_variable(profile_id, name, VarValue::Bool(value));
+    });
+}
@@ -1,10 +1,12 @@
 use std::sync::OnceLock;
 
 use crossbeam_channel::{unbounded, Receiver, Sender};
-use rdev::Event;
+use parking_lot::Mutex;
+use rdev::{Event, EventType};
 
 static HOTKEY_SENDER: OnceLock<Sender<Event>> = OnceLock::new();
 static HOTKEY_RECEIVER: OnceLock<Receiver<Event>> = OnceLock::new();
+static INPUT_SUBSCRIBERS: Mutex<Vec<Sender<EventType>>> = Mutex::new(Vec::new());
 
 pub fn init_event_channel() {
     let (tx, rx) = unbounded::<Event>();
@@ -21,3 +23,15 @@ pub fn get_event_sender() -> &'static Sender<Event> {
 pub fn get_event_receiver() -> &'static Receiver<Event> {
     HOTKEY_RECEIVER.get().expect("Canal no inicializado")
 }
+
+/// Recibe cada pulsación y liberación después de actualizar el estado de teclas.
+/// La suscripción termina al soltar el receptor.
+pub fn subscribe_input() -> Receiver<EventType> {
+    let (tx, rx) = unbounded();
+    INPUT_SUBSCRIBERS.lock().push(tx);
+    rx
+}
+
+pub(crate) fn publish_input(ev: &EventType) {
+    INPUT_SUBSCRIBERS.lock().retain(|tx| tx.send(*ev).is_ok());
+}
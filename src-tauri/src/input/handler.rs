@@ -6,10 +6,10 @@ use std::{
 use crate::{
     config::handler::get_config,
     input::{
-        global::{get_event_receiver, get_event_sender},
-        state::{is_combo_completed, KeyState},
+        global::{get_event_receiver, get_event_sender, publish_input},
+        state::{is_combo_completed, KeyState, CURSOR},
     },
-    keys,
+    keys::{self, InputKey},
 };
 use anyhow::Result;
 use crossbeam_channel::Sender;
@@ -20,11 +20,13 @@ use tracing::{debug, error, info, warn};
 #[cfg(windows)]
 use winapi::um::processthreadsapi::{GetCurrentThread, SetThreadPriority};
 
-type SharedState = Arc<Mutex<KeyState>>;
+pub type SharedState = Arc<Mutex<KeyState>>;
 
 #[derive(Debug)]
 pub enum HotkeyEvent {
-    ComboTriggered(u64),
+    /// Macro disparada y cuándo llegó la pulsación, en microsegundos del
+    /// reloj del ejecutor.
+    ComboTriggered(u64, u64),
     ProfileSwitch(u64),
 }
 
@@ -36,9 +38,10 @@ pub struct KeyboardHandler {
 }
 
 impl KeyboardHandler {
-    pub fn new() -> Self {
+    /// `state` se actualiza con lo que llega del teclado; su reloj debe ser
+    /// el del ejecutor.
+    pub fn new(state: SharedState) -> Self {
         info!("Creando handler de teclado");
-        let state: SharedState = Arc::new(Mutex::new(KeyState::default()));
         Self {
             state,
             tx_event: None,
@@ -74,11 +77,22 @@ impl KeyboardHandler {
                     // Hilo para el listener de teclado (scoped)
                     s.spawn(move |_| {
                         if let Err(e) = rdev::grab(move |ev: Event| {
-                            if let EventType::KeyPress(_) = ev.event_type {
-                                if let Err(e) = internal_tx.send(ev.clone()) {
-                                    error!(error = ?e, "Error enviando evento interno");
-                                    return Some(ev);
+                            // Las liberaciones también pasan para que el estado no se quede con teclas pegadas
+                            match ev.event_type {
+                                EventType::KeyPress(_)
+                                | EventType::KeyRelease(_)
+                                | EventType::ButtonPress(_)
+                                | EventType::ButtonRelease(_) => {
+                                    if let Err(e) = internal_tx.send(ev.clone()) {
+                                        error!(error = ?e, "Error enviando evento interno");
+                                        return Some(ev);
+                                    }
+                                }
+                                // Demasiado frecuentes para el canal; basta con la última posición
+                                EventType::MouseMove { x, y } => {
+                                    *CURSOR.lock() = Some((x, y));
                                 }
+                                EventType::Wheel { .. } => {}
                             }
 
                             Some(ev)
@@ -95,6 +109,7 @@ impl KeyboardHandler {
                                     Ok(ev) => {
                                         let mut st = state.lock();
                                         st.update(&ev);
+                                        publish_input(&ev.event_type);
 
                                         if let Err(e) = Self::process_event(&tx_raw, &ev, &st) {
                                             error!(error = %e, "Error procesando evento");
@@ -130,15 +145,17 @@ impl KeyboardHandler {
         let span = tracing::debug_span!("process_event", ?ev);
         let _enter = span.enter();
 
-        let EventType::KeyPress(key) = ev.event_type else {
-            return Ok(());
+        let key = match ev.event_type {
+            EventType::KeyPress(k) => InputKey::Key(k),
+            EventType::ButtonPress(b) => InputKey::Button(b),
+            _ => return Ok(()),
         };
 
         let cfg = get_config();
 
         for (profile_id, profile) in &cfg.profiles {
             if let Some(function_key) = &profile.function_key {
-                let switch_key = keys::str_to_key(function_key);
+                let switch_key = keys::str_to_input(function_key);
                 // trace!(
                 //     ?profile_id,
                 //     ?function_key,
@@ -172,7 +189,9 @@ impl KeyboardHandler {
                 if keys::key_matches(&mac.trigger.key, key) && is_combo_completed(st, &mac.trigger)
                 {
                     info!(macro_id = mac.id, "Macro activada");
-                    if let Err(e) = tx.send(HotkeyEvent::ComboTriggered(mac.id)) {
+                    if let Err(e) =
+                        tx.send(HotkeyEvent::ComboTriggered(mac.id, st.pressed_at_us(key)))
+                    {
                         warn!("Error enviando evento de macro: {:?}", e);
                     }
                     return Ok(());
@@ -1,35 +1,181 @@
-use ahash::AHashSet;
+use std::sync::{Arc, LazyLock};
+
+use ahash::AHashMap;
+use parking_lot::Mutex;
 use rdev::EventType;
 
-use crate::{domain::macros::KeyCombination, input::state, keys};
+use crate::{
+    domain::macros::KeyCombination,
+    engine::clock::{self, Clock},
+    input::state,
+    keys::{self, InputKey},
+};
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct KeyState {
-    pressed: AHashSet<rdev::Key>,
+    /// Teclas pulsadas y cuándo se pulsaron, en microsegundos de `clock`.
+    pressed: AHashMap<InputKey, u64>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for KeyState {
+    fn default() -> Self {
+        Self::new(clock::system())
+    }
 }
 
+/// Última posición conocida del cursor, para los movimientos relativos.
+pub(crate) static CURSOR: Mutex<Option<(f64, f64)>> = Mutex::new(None);
+
+/// Teclas pulsadas ahora mismo. Lo actualiza el listener y lo consulta el
+/// ejecutor; marca las pulsaciones con [`clock::system`], el reloj del ejecutor
+/// de la aplicación.
+pub(crate) static KEY_STATE: LazyLock<Arc<Mutex<KeyState>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(KeyState::default())));
+
 impl KeyState {
+    /// `clock` marca las pulsaciones; debe ser el del ejecutor para que la
+    /// latencia de los disparos se mida en la misma escala.
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            pressed: AHashMap::new(),
+            clock,
+        }
+    }
+
+    #[inline]
+    pub fn is_pressed(&self, key: InputKey) -> bool {
+        self.pressed.contains_key(&key)
+    }
+
+    /// Cuándo se pulsó `key`; si no consta pulsada, ahora.
+    pub fn pressed_at_us(&self, key: InputKey) -> u64 {
+        self.pressed
+            .get(&key)
+            .copied()
+            .unwrap_or_else(|| self.clock.now_us())
+    }
+
     pub fn update(&mut self, ev: &rdev::Event) {
-        match ev.event_type {
-            EventType::KeyPress(k) => {
-                self.pressed.insert(k);
+        match transition(&ev.event_type) {
+            Some((key, true)) => {
+                self.pressed.insert(key, self.clock.now_us());
             }
-            EventType::KeyRelease(k) => {
-                self.pressed.remove(&k);
+            Some((key, false)) => {
+                self.pressed.remove(&key);
             }
-            _ => {}
+            None => {}
         }
     }
 }
 
+/// Tecla o botón afectado y si se pulsó (`true`) o se soltó.
+pub fn transition(ev: &EventType) -> Option<(InputKey, bool)> {
+    match *ev {
+        EventType::KeyPress(k) => Some((InputKey::Key(k), true)),
+        EventType::KeyRelease(k) => Some((InputKey::Key(k), false)),
+        EventType::ButtonPress(b) => Some((InputKey::Button(b), true)),
+        EventType::ButtonRelease(b) => Some((InputKey::Button(b), false)),
+        _ => None,
+    }
+}
+
 pub(crate) fn is_combo_completed(state: &state::KeyState, trigger: &KeyCombination) -> bool {
-    let base = keys::str_to_key(&trigger.key);
-    if !state.pressed.contains(&base) {
+    let base = keys::str_to_input(&trigger.key);
+    if !state.pressed.contains_key(&base) {
         return false;
     }
 
     trigger.modifiers.iter().all(|m| {
         let k = keys::mod_to_key(m);
-        state.pressed.contains(&k)
+        state.pressed.contains_key(&InputKey::Key(k))
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rdev::{Button, Event, Key};
+
+    use super::*;
+    use crate::{domain::enums::ModifierKey, engine::clock::ManualClock};
+
+    fn event(event_type: EventType) -> Event {
+        Event {
+            time: std::time::SystemTime::UNIX_EPOCH,
+            name: None,
+            event_type,
+        }
+    }
+
+    fn state() -> (KeyState, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::default());
+        (KeyState::new(clock.clone()), clock)
+    }
+
+    fn combo(modifiers: Vec<ModifierKey>, key: &str) -> KeyCombination {
+        KeyCombination {
+            modifiers,
+            key: key.into(),
+        }
+    }
+
+    #[test]
+    fn presses_are_stamped_with_the_injected_clock() {
+        let (mut state, clock) = state();
+        clock.advance(Duration::from_millis(5));
+        state.update(&event(EventType::KeyPress(Key::KeyA)));
+        clock.advance(Duration::from_millis(3));
+        state.update(&event(EventType::ButtonPress(Button::Left)));
+        clock.advance(Duration::from_millis(2));
+
+        assert_eq!(state.pressed_at_us(InputKey::Key(Key::KeyA)), 5_000);
+        assert_eq!(state.pressed_at_us(InputKey::Button(Button::Left)), 8_000);
+        // Lo que no consta pulsado se da por pulsado ahora
+        assert_eq!(state.pressed_at_us(InputKey::Key(Key::KeyB)), 10_000);
+    }
+
+    #[test]
+    fn repeated_presses_restamp_and_releases_forget() {
+        let (mut state, clock) = state();
+        let a = InputKey::Key(Key::KeyA);
+        state.update(&event(EventType::KeyPress(Key::KeyA)));
+        clock.advance(Duration::from_millis(30));
+        state.update(&event(EventType::KeyPress(Key::KeyA)));
+        assert_eq!(state.pressed_at_us(a), 30_000);
+
+        clock.advance(Duration::from_millis(10));
+        state.update(&event(EventType::KeyRelease(Key::KeyA)));
+        assert!(!state.is_pressed(a));
+        assert_eq!(state.pressed_at_us(a), 40_000);
+    }
+
+    #[test]
+    fn combos_need_the_key_and_every_modifier() {
+        let (mut state, _) = state();
+        let trigger = combo(vec![ModifierKey::Ctrl, ModifierKey::Shift], "Q");
+        assert!(!is_combo_completed(&state, &trigger));
+
+        state.update(&event(EventType::KeyPress(Key::KeyQ)));
+        state.update(&event(EventType::KeyPress(Key::ControlLeft)));
+        assert!(!is_combo_completed(&state, &trigger));
+
+        state.update(&event(EventType::KeyPress(Key::ShiftLeft)));
+        assert!(is_combo_completed(&state, &trigger));
+        assert!(is_combo_completed(&state, &combo(Vec::new(), "Q")));
+
+        state.update(&event(EventType::KeyRelease(Key::KeyQ)));
+        assert!(!is_combo_completed(&state, &trigger));
+    }
+
+    #[test]
+    fn mouse_buttons_trigger_combos() {
+        let (mut state, _) = state();
+        let trigger = combo(vec![ModifierKey::Alt], "MOUSE4");
+        state.update(&event(EventType::KeyPress(Key::Alt)));
+        state.update(&event(EventType::ButtonPress(Button::Unknown(1))));
+
+        assert!(is_combo_completed(&state, &trigger));
+    }
+}
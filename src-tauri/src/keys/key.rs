@@ -1,5 +1,5 @@
-use phf::phf_map;
-use rdev::Key;
+use phf::{phf_map, phf_set};
+use rdev::{Button, Key};
 
 use crate::domain::enums::ModifierKey;
 
@@ -34,7 +34,7 @@ static STR_TO_KEY: phf::Map<&'static str, Key> = phf_map! {
     "ENTER" => Key::Return, "\n" => Key::Return, "\r" => Key::Return,
     "ESC" => Key::Escape, "ESCAPE" => Key::Escape,
     "BACKSPACE" => Key::Backspace,
-    "TAB" => Key::Tab,
+    "TAB" => Key::Tab, "\t" => Key::Tab,
     "SPACE" => Key::Space, " " => Key::Space,
     "CAPSLOCK" => Key::CapsLock,
     "SHIFT" => Key::ShiftLeft,
@@ -90,6 +90,28 @@ static STR_TO_KEY: phf::Map<&'static str, Key> = phf_map! {
     "PAUSE" => Key::Pause,
 };
 
+/// Botones del ratón. `MOUSE4`/`MOUSE5` son los laterales (XBUTTON1/2 en Windows).
+static STR_TO_BUTTON: phf::Map<&'static str, Button> = phf_map! {
+    "MOUSE1" => Button::Left, "MOUSE_LEFT" => Button::Left, "LMB" => Button::Left,
+    "MOUSE2" => Button::Right, "MOUSE_RIGHT" => Button::Right, "RMB" => Button::Right,
+    "MOUSE3" => Button::Middle, "MOUSE_MIDDLE" => Button::Middle, "MMB" => Button::Middle,
+    "MOUSE4" => Button::Unknown(1), "MOUSE_BACK" => Button::Unknown(1),
+    "MOUSE5" => Button::Unknown(2), "MOUSE_FORWARD" => Button::Unknown(2),
+};
+
+/// Tecla o botón del ratón; ambos pueden formar parte de un disparador.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputKey {
+    Key(Key),
+    Button(Button),
+}
+
+/// Caracteres de `STR_TO_KEY` que en la distribución US necesitan Shift.
+static US_SHIFTED: phf::Set<char> = phf_set! {
+    ')', '!', '@', '#', '$', '%', '^', '&', '*', '(',
+    '~', '_', '+', '{', '}', '|', ':', '"', '<', '>', '?',
+};
+
 pub fn str_to_key(s: &str) -> Key {
     STR_TO_KEY
         .get(&*s.to_uppercase())
@@ -97,6 +119,13 @@ pub fn str_to_key(s: &str) -> Key {
         .unwrap_or(Key::Unknown(0))
 }
 
+/// Tecla física y si hace falta Shift para escribir `c` con la distribución US.
+pub(super) fn char_to_us_key(c: char) -> Option<(Key, bool)> {
+    let key = *STR_TO_KEY.get(&*c.to_uppercase().to_string())?;
+    let shift = c.is_ascii_uppercase() || US_SHIFTED.contains(&c);
+    Some((key, shift))
+}
+
 pub fn mod_to_key(key: &ModifierKey) -> Key {
     match key {
         ModifierKey::Alt => Key::Alt,
@@ -106,8 +135,90 @@ pub fn mod_to_key(key: &ModifierKey) -> Key {
     }
 }
 
-pub fn key_matches(expected: &str, actual: rdev::Key) -> bool {
-    let expected = str_to_key(expected);
+pub fn str_to_button(s: &str) -> Option<Button> {
+    STR_TO_BUTTON.get(&*s.to_uppercase()).copied()
+}
+
+/// Los nombres de botones tienen prioridad; el resto se interpreta como tecla.
+pub fn str_to_input(s: &str) -> InputKey {
+    str_to_button(s).map_or_else(|| InputKey::Key(str_to_key(s)), InputKey::Button)
+}
+
+/// Entradas que cuentan como `s` pulsada. Los nombres genéricos de
+/// modificador aceptan también la tecla del lado derecho.
+pub fn str_to_held_inputs(s: &str) -> Vec<InputKey> {
+    let sides = match &*s.to_uppercase() {
+        "SHIFT" => [Key::ShiftLeft, Key::ShiftRight],
+        "CTRL" | "CONTROL" => [Key::ControlLeft, Key::ControlRight],
+        "GUI" | "SUPER" | "WIN" | "WINDOWS" | "COMMAND" => [Key::MetaLeft, Key::MetaRight],
+        _ => return vec![str_to_input(s)],
+    };
+    sides.into_iter().map(InputKey::Key).collect()
+}
+
+pub fn key_matches(expected: &str, actual: InputKey) -> bool {
+    let expected = str_to_input(expected);
 
     expected == actual
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn held_inputs_accept_both_sides_of_generic_modifiers() {
+        let cases: [(&str, &[InputKey]); 9] = [
+            (
+                "SHIFT",
+                &[
+                    InputKey::Key(Key::ShiftLeft),
+                    InputKey::Key(Key::ShiftRight),
+                ],
+            ),
+            (
+                "ctrl",
+                &[
+                    InputKey::Key(Key::ControlLeft),
+                    InputKey::Key(Key::ControlRight),
+                ],
+            ),
+            (
+                "Control",
+                &[
+                    InputKey::Key(Key::ControlLeft),
+                    InputKey::Key(Key::ControlRight),
+                ],
+            ),
+            (
+                "GUI",
+                &[InputKey::Key(Key::MetaLeft), InputKey::Key(Key::MetaRight)],
+            ),
+            (
+                "win",
+                &[InputKey::Key(Key::MetaLeft), InputKey::Key(Key::MetaRight)],
+            ),
+            ("A", &[InputKey::Key(Key::KeyA)]),
+            ("!", &[InputKey::Key(Key::Num1)]),
+            ("MOUSE4", &[InputKey::Button(Button::Unknown(1))]),
+            ("NOPE", &[InputKey::Key(Key::Unknown(0))]),
+        ];
+
+        for (name, expected) in cases {
+            assert_eq!(str_to_held_inputs(name), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn us_shifted_characters() {
+        for c in "!@#$%^&*()~_+{}|:\"<>?".chars() {
+            assert!(matches!(char_to_us_key(c), Some((_, true))), "{c:?}");
+        }
+        for c in r"1234567890`-=[]\;',./ ".chars() {
+            assert!(matches!(char_to_us_key(c), Some((_, false))), "{c:?}");
+        }
+        assert_eq!(char_to_us_key('Z'), Some((Key::KeyZ, true)));
+        assert_eq!(char_to_us_key('z'), Some((Key::KeyZ, false)));
+        assert_eq!(char_to_us_key('ü'), None);
+    }
+}
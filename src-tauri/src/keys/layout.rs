@@ -0,0 +1,141 @@
+use phf::phf_map;
+use rdev::Key;
+
+use super::key::char_to_us_key;
+use crate::domain::enums::KeyboardLayout;
+
+/// Modificador necesario para obtener un carácter de una tecla.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Base,
+    Shift,
+    AltGr,
+}
+
+impl Level {
+    pub fn modifier(self) -> Option<Key> {
+        match self {
+            Level::Base => None,
+            Level::Shift => Some(Key::ShiftLeft),
+            Level::AltGr => Some(Key::AltGr),
+        }
+    }
+}
+
+/// Símbolos de la distribución española (ISO). Los que solo existen como
+/// tecla muerta (`´`, `` ` ``, `^`, `¨`) no se incluyen.
+static ES_SYMBOLS: phf::Map<char, (Key, Level)> = phf_map! {
+    'º' => (Key::BackQuote, Level::Base), 'ª' => (Key::BackQuote, Level::Shift),
+    '\\' => (Key::BackQuote, Level::AltGr),
+    '!' => (Key::Num1, Level::Shift), '|' => (Key::Num1, Level::AltGr),
+    '"' => (Key::Num2, Level::Shift), '@' => (Key::Num2, Level::AltGr),
+    '·' => (Key::Num3, Level::Shift), '#' => (Key::Num3, Level::AltGr),
+    '$' => (Key::Num4, Level::Shift), '~' => (Key::Num4, Level::AltGr),
+    '%' => (Key::Num5, Level::Shift),
+    '&' => (Key::Num6, Level::Shift), '¬' => (Key::Num6, Level::AltGr),
+    '/' => (Key::Num7, Level::Shift),
+    '(' => (Key::Num8, Level::Shift),
+    ')' => (Key::Num9, Level::Shift),
+    '=' => (Key::Num0, Level::Shift),
+    '\'' => (Key::Minus, Level::Base), '?' => (Key::Minus, Level::Shift),
+    '¡' => (Key::Equal, Level::Base), '¿' => (Key::Equal, Level::Shift),
+    '[' => (Key::LeftBracket, Level::AltGr),
+    '+' => (Key::RightBracket, Level::Base), '*' => (Key::RightBracket, Level::Shift),
+    ']' => (Key::RightBracket, Level::AltGr),
+    'ñ' => (Key::SemiColon, Level::Base), 'Ñ' => (Key::SemiColon, Level::Shift),
+    '{' => (Key::Quote, Level::AltGr),
+    'ç' => (Key::BackSlash, Level::Base), 'Ç' => (Key::BackSlash, Level::Shift),
+    '}' => (Key::BackSlash, Level::AltGr),
+    '<' => (Key::IntlBackslash, Level::Base), '>' => (Key::IntlBackslash, Level::Shift),
+    ',' => (Key::Comma, Level::Base), ';' => (Key::Comma, Level::Shift),
+    '.' => (Key::Dot, Level::Base), ':' => (Key::Dot, Level::Shift),
+    '-' => (Key::Slash, Level::Base), '_' => (Key::Slash, Level::Shift),
+    '€' => (Key::KeyE, Level::AltGr),
+};
+
+/// Tecla física y modificador con los que se escribe `c` en la distribución
+/// indicada, o `None` si no se puede escribir con una sola pulsación.
+pub fn char_to_key(c: char, layout: KeyboardLayout) -> Option<(Key, Level)> {
+    match layout {
+        KeyboardLayout::Us => char_to_us_key(c).map(|(key, shift)| {
+            let level = if shift { Level::Shift } else { Level::Base };
+            (key, level)
+        }),
+        KeyboardLayout::Es => ES_SYMBOLS.get(&c).copied().or_else(|| {
+            // Letras, dígitos y espacios están donde en US
+            let (key, shift) =
+                char_to_us_key(c).filter(|_| c.is_alphanumeric() || c.is_whitespace())?;
+            let level = if shift { Level::Shift } else { Level::Base };
+            Some((key, level))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn us_layout() {
+        let cases = [
+            ('a', Some((Key::KeyA, Level::Base))),
+            ('A', Some((Key::KeyA, Level::Shift))),
+            ('1', Some((Key::Num1, Level::Base))),
+            ('!', Some((Key::Num1, Level::Shift))),
+            ('@', Some((Key::Num2, Level::Shift))),
+            ('^', Some((Key::Num6, Level::Shift))),
+            ('-', Some((Key::Minus, Level::Base))),
+            ('_', Some((Key::Minus, Level::Shift))),
+            ('/', Some((Key::Slash, Level::Base))),
+            ('?', Some((Key::Slash, Level::Shift))),
+            ('"', Some((Key::Quote, Level::Shift))),
+            ('~', Some((Key::BackQuote, Level::Shift))),
+            (' ', Some((Key::Space, Level::Base))),
+            ('\n', Some((Key::Return, Level::Base))),
+            ('ñ', None),
+            ('€', None),
+            ('é', None),
+        ];
+
+        for (c, expected) in cases {
+            assert_eq!(char_to_key(c, KeyboardLayout::Us), expected, "{c:?}");
+        }
+    }
+
+    #[test]
+    fn es_layout() {
+        let cases = [
+            ('a', Some((Key::KeyA, Level::Base))),
+            ('A', Some((Key::KeyA, Level::Shift))),
+            ('1', Some((Key::Num1, Level::Base))),
+            ('!', Some((Key::Num1, Level::Shift))),
+            ('|', Some((Key::Num1, Level::AltGr))),
+            ('@', Some((Key::Num2, Level::AltGr))),
+            ('"', Some((Key::Num2, Level::Shift))),
+            ('/', Some((Key::Num7, Level::Shift))),
+            ('?', Some((Key::Minus, Level::Shift))),
+            ('-', Some((Key::Slash, Level::Base))),
+            ('ñ', Some((Key::SemiColon, Level::Base))),
+            ('Ñ', Some((Key::SemiColon, Level::Shift))),
+            ('€', Some((Key::KeyE, Level::AltGr))),
+            ('<', Some((Key::IntlBackslash, Level::Base))),
+            (' ', Some((Key::Space, Level::Base))),
+            // Símbolos US que en ES están en otra tecla o no existen
+            ('^', None),
+            ('`', None),
+            ('´', None),
+            ('é', None),
+        ];
+
+        for (c, expected) in cases {
+            assert_eq!(char_to_key(c, KeyboardLayout::Es), expected, "{c:?}");
+        }
+    }
+
+    #[test]
+    fn levels_map_to_modifiers() {
+        assert_eq!(Level::Base.modifier(), None);
+        assert_eq!(Level::Shift.modifier(), Some(Key::ShiftLeft));
+        assert_eq!(Level::AltGr.modifier(), Some(Key::AltGr));
+    }
+}
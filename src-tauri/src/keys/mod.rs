@@ -1,5 +1,11 @@
 pub mod enum_key;
 mod key;
+mod layout;
 pub use key::key_matches;
 pub use key::mod_to_key;
+pub use key::str_to_button;
+pub use key::str_to_held_inputs;
+pub use key::str_to_input;
 pub use key::str_to_key;
+pub use key::InputKey;
+pub use layout::char_to_key;
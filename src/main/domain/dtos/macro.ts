@@ -14,12 +14,14 @@ export class MacroDto
   name: string;
   triggerKey: string;
   sequence: SequenceStepDto[];
+  script?: string;
 
   constructor(value: Type<MacroDto>) {
     this.id = value.id;
     this.name = value.name;
     this.triggerKey = value.triggerKey;
     this.sequence = value.sequence.map((s) => new SequenceStepDto(s));
+    this.script = value.script;
   }
 
   static fromData(macro: Macro): MacroDto {
@@ -28,6 +30,7 @@ export class MacroDto
       name: macro.name,
       triggerKey: MacroDto.stringifyKeyCombination(macro.trigger),
       sequence: macro.sequence.map(SequenceStepDto.fromData),
+      script: macro.script,
     });
   }
 
@@ -41,6 +44,7 @@ export class MacroDto
       name: this.name,
       trigger: MacroDto.parseTriggerKey(this.triggerKey),
       sequence: this.sequence.map((s) => s.toData()),
+      script: this.script,
     };
   }
 
@@ -14,6 +14,7 @@ export class ProfileDto
   functionKey: Nullable<FunctionKey>;
   active?: boolean;
   macros: MacroDto[];
+  persistVariables?: boolean;
 
   constructor(value: Type<ProfileDto>) {
     this.id = value.id;
@@ -21,6 +22,7 @@ export class ProfileDto
     this.functionKey = value.functionKey;
     this.active = value.active ?? false;
     this.macros = value.macros.map((m) => new MacroDto(m));
+    this.persistVariables = value.persistVariables ?? false;
   }
 
   static fromData(profile: Profile): ProfileDto {
@@ -29,6 +31,7 @@ export class ProfileDto
       name: profile.name || null,
       functionKey: profile.functionKey,
       macros: profile.macros.map(MacroDto.fromData),
+      persistVariables: profile.persistVariables,
     });
   }
 
@@ -45,6 +48,7 @@ export class ProfileDto
       name: this.name,
       functionKey: this.functionKey,
       macros: this.macros.map((m) => m.toData()),
+      persistVariables: this.persistVariables ?? false,
     };
   }
 
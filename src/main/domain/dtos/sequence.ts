@@ -10,15 +10,18 @@ export class SequenceStepDto
     IFormConvertible<SequenceStepSchema>
 {
   id: number;
-  type: 'keydown' | 'keyup' | 'delay';
+  type: SequenceStep['type'];
   key?: string;
   delay?: number;
+  // Pasos que el editor aún no sabe editar se conservan tal cual
+  raw?: SequenceStep;
 
   constructor(value: Type<SequenceStepDto>) {
     this.id = value.id;
     this.type = value.type;
     this.key = value.key;
     this.delay = value.delay;
+    this.raw = value.raw;
   }
 
   static create(value: Type<SequenceStepDto>) {
@@ -26,7 +29,8 @@ export class SequenceStepDto
   }
 
   static fromData(step: SequenceStep): SequenceStepDto {
-    if (step.type === 'delay') {
+    // Los retardos aleatorios aún no se editan aquí; pasan como `raw`
+    if (step.type === 'delay' && typeof step.ms === 'number') {
       return new SequenceStepDto({
         id: step.id,
         type: step.type,
@@ -39,7 +43,7 @@ export class SequenceStepDto
         key: step.key,
       });
     }
-    throw new Error(`Unknown step type: ${(step as SequenceStepDto).type}`);
+    return new SequenceStepDto({ id: step.id, type: step.type, raw: step });
   }
 
   toData(): SequenceStep {
@@ -58,6 +62,9 @@ export class SequenceStepDto
         return { type: this.type, id: this.id, ms: this.delay };
 
       default:
+        if (this.raw !== undefined) {
+          return { ...this.raw, id: this.id };
+        }
         throw new Error(`Unknown step type: ${(this as SequenceStepDto).type}`);
     }
   }
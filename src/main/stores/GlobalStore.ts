@@ -4,6 +4,8 @@ import {
   loadConfig,
   saveConfig,
 } from '@/main/services/config';
+import { KeyboardLayout } from '@/shared/bindings/KeyboardLayout';
+import { Macro } from '@/shared/bindings/Macro';
 import { nullable } from '@/shared/helpers/nullable';
 import { watch } from '@/shared/services/watch';
 import { Nullable } from '@/shared/types/utils';
@@ -26,6 +28,9 @@ export interface DataState {
 export type GlobalState = {
   status: StatusType;
   error: Nullable<string>;
+  // Biblioteca y distribución de teclado; se conservan tal cual al guardar
+  library: Macro[];
+  keyboardLayout: KeyboardLayout;
 } & DataState;
 
 export type GlobalActions = {
@@ -46,6 +51,8 @@ const useGlobalStore = create<GlobalStore>()(
   subscribeWithSelector((set, get) => ({
     status: 'idle',
     error: null,
+    library: [],
+    keyboardLayout: 'Us',
     profiles: [],
     selectedProfileId: 0,
     selectedMacroId: null,
@@ -99,7 +106,11 @@ const useGlobalStore = create<GlobalStore>()(
               })
           );
         dispatch({ type: MacroActionType.SET_PROFILES, payload: profiles });
-        set({ status: 'idle' });
+        set({
+          status: 'idle',
+          library: config.library,
+          keyboardLayout: config.keyboardLayout,
+        });
       } catch (err) {
         const error = 'Failed to load profiles';
         console.error(error, err);
@@ -107,7 +118,7 @@ const useGlobalStore = create<GlobalStore>()(
       }
     },
     async saveChanges() {
-      const { status, profiles } = get();
+      const { status, profiles, library, keyboardLayout } = get();
       if (status === 'loading') return;
       set({ status: 'saving', error: null });
       try {
@@ -117,6 +128,8 @@ const useGlobalStore = create<GlobalStore>()(
         const saveResult = await saveConfig({
           profiles: profilesData,
           selectedProfileId: nullable(activeProfile?.id),
+          library,
+          keyboardLayout,
         });
 
         if (saveResult.isErr()) throw new Error(saveResult.error);
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type BackupInfo = {
+  fileName: string;
+  /**
+   * Milisegundos desde UNIX_EPOCH.
+   */
+  timestamp: number;
+  size: number;
+};
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Comparación de una condición `Var`.
+ */
+export type Compare = "eq" | "ne" | "lt" | "le" | "gt" | "ge";
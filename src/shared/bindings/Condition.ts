@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Compare } from "./Compare";
+import type { Side } from "./Side";
+import type { VarValue } from "./VarValue";
+
+/**
+ * Condición de un paso `If`. Se evalúa al llegar al paso, sin esperar.
+ */
+export type Condition =
+  | { type: "held"; key: string }
+  | { type: "facing"; side: Side }
+  | { type: "var"; name: string; op: Compare; value: VarValue }
+  | { type: "not"; condition: Condition }
+  | { type: "all"; conditions: Array<Condition> }
+  | { type: "any"; conditions: Array<Condition> };
@@ -1,9 +1,16 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import { Nullable } from "@/shared/types/utils";
+import type { KeyboardLayout } from "./KeyboardLayout";
+import type { Macro } from "./Macro";
 import type { Profile } from "./Profile";
 
 export type Config = {
   profiles: Array<Profile>;
   selectedProfileId: Nullable<number>;
+  /**
+   * Macros reutilizables que no pertenecen a ningún perfil. No se disparan
+   * con teclas; solo se ejecutan desde un paso `CallMacro`.
+   */
+  library: Array<Macro>;
+  keyboardLayout: KeyboardLayout;
 };
-
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Cambios detectados al recargar la configuración desde disco.
+ */
+export type ConfigDiff = {
+  addedProfiles: Array<number>;
+  removedProfiles: Array<number>;
+  changedProfiles: Array<number>;
+  profilesReordered: boolean;
+  addedMacros: Array<number>;
+  removedMacros: Array<number>;
+  changedMacros: Array<number>;
+  libraryReordered: boolean;
+  keyboardLayoutChanged: boolean;
+  selectedProfileChanged: boolean;
+};
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Estado de recuperación cuando `config.json` no se pudo leer.
+ *
+ * Mientras exista, el guardado queda bloqueado para no sobrescribir el
+ * archivo original del usuario.
+ */
+export type ConfigRecovery = {
+  message: string;
+  line: number | null;
+  column: number | null;
+  /**
+   * Adonde se apartó el archivo dañado, con marca de tiempo, si se pudo renombrar.
+   */
+  backupPath: string | null;
+};
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HistoryEntry } from "./HistoryEntry";
+
+/**
+ * Registro de ediciones para la UI; el último elemento es el más reciente.
+ */
+export type HistoryLog = {
+  undo: Array<HistoryEntry>;
+  redo: Array<HistoryEntry>;
+};
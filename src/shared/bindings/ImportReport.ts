@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TriggerConflict } from "./TriggerConflict";
+
+export type ImportReport = {
+  profileId: number;
+  /**
+   * IDs nuevos asignados a las macros importadas.
+   */
+  macroIds: Array<number>;
+  /**
+   * IDs nuevos de las macros llamadas, creadas en la biblioteca.
+   */
+  libraryMacroIds: Array<number>;
+  conflicts: Array<TriggerConflict>;
+  /**
+   * La tecla de función del perfil importado ya la usaba otro perfil y se descartó.
+   */
+  functionKeyDropped: boolean;
+};
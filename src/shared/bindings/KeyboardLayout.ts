@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Distribución del teclado del usuario, para escribir texto con `TypeText`.
+ */
+export type KeyboardLayout = "Us" | "Es";
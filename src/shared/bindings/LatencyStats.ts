@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Percentiles } from "./Percentiles";
+
+/**
+ * Estadísticas sobre las ejecuciones del historial.
+ */
+export type LatencyStats = {
+  runs: number;
+  /**
+   * Solo ejecuciones disparadas por teclado que llegaron a enviar algo.
+   */
+  triggerLatency: Percentiles | null;
+  /**
+   * Desviación de cada paso respecto a su inicio previsto.
+   */
+  stepJitter: Percentiles | null;
+};
@@ -1,3 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type ListenableChannel = 'selected-profile-changed' | 'sequence-step';
+export type ListenableChannel =
+  | 'selected-profile-changed'
+  | 'sequence-step'
+  | 'config-recovery'
+  | 'config-reloaded';
@@ -7,5 +7,9 @@ export type Macro = {
   name: string;
   trigger: KeyCombination;
   sequence: Array<SequenceStep>;
+  /**
+   * Script Rhai que sustituye a `sequence` al ejecutar la macro.
+   */
+  script?: string;
 };
 
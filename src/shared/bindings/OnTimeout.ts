@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Qué hacer si `WaitForKey` agota el tiempo.
+ */
+export type OnTimeout = "abort" | "continue";
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Evento que la macro habría enviado al sistema.
+ */
+export type OutputEvent =
+  | { type: "keyDown"; key: string }
+  | { type: "keyUp"; key: string }
+  | { type: "buttonDown"; button: string }
+  | { type: "buttonUp"; button: string }
+  | { type: "mouseMove"; x: number; y: number }
+  | { type: "scroll"; dx: number; dy: number };
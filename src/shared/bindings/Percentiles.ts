@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Percentiles en microsegundos.
+ */
+export type Percentiles = { p50: number; p95: number; p99: number; max: number };
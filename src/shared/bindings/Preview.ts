@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TimelineEntry } from "./TimelineEntry";
+
+/**
+ * Resultado de ejecutar una macro sin enviar nada al sistema.
+ */
+export type Preview = {
+  timeline: Array<TimelineEntry>;
+  durationUs: number;
+  /**
+   * Error con el que se habría detenido la macro, si lo hubo.
+   */
+  error: string | null;
+  /**
+   * La macro pasó los límites de la vista previa y no se muestra entera.
+   */
+  truncated: boolean;
+};
@@ -7,5 +7,9 @@ export type Profile = {
   name: string;
   functionKey: FunctionKey | null;
   macros: Array<Macro>;
+  /**
+   * Conserva las variables de ejecución del perfil entre reinicios.
+   */
+  persistVariables: boolean;
 };
 
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Resultado de una mutación junto con la revisión resultante de la configuración.
+ */
+export type Revisioned<T> = { revision: number; data: T };
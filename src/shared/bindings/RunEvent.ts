@@ -0,0 +1,34 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OutputEvent } from "./OutputEvent";
+import type { RunOutcome } from "./RunOutcome";
+
+/**
+ * Progreso de una ejecución; llega a la UI dentro de un [`RunEventBatch`].
+ * Las marcas de tiempo son microsegundos desde la época Unix.
+ */
+export type RunEvent =
+  | { kind: "runStarted"; runId: number; macroId: number | null; atUs: number }
+  | {
+      kind: "stepStarted";
+      runId: number;
+      stepId: number;
+      index: number;
+      depth: number;
+      atUs: number;
+    }
+  | {
+      kind: "stepFinished";
+      runId: number;
+      stepId: number;
+      index: number;
+      depth: number;
+      atUs: number;
+    }
+  | {
+      kind: "runFinished";
+      runId: number;
+      outcome: RunOutcome;
+      error: string | null;
+      atUs: number;
+    }
+  | { kind: "output"; event: OutputEvent; atUs: number };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RunEvent } from "./RunEvent";
+
+/**
+ * Carga del canal `sequence-step`: los eventos de un intervalo de emisión, en
+ * el orden en que ocurrieron.
+ */
+export type RunEventBatch = Array<RunEvent>;
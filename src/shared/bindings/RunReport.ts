@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RunOutcome } from "./RunOutcome";
+import type { StepTiming } from "./StepTiming";
+
+/**
+ * Informe de tiempos de una ejecución terminada.
+ */
+export type RunReport = {
+  runId: number;
+  macroId: number | null;
+  /**
+   * Microsegundos desde la época Unix.
+   */
+  startedAtUs: number;
+  durationUs: number;
+  outcome: RunOutcome;
+  /**
+   * Desde la pulsación que disparó la macro hasta la primera tecla enviada.
+   */
+  triggerLatencyUs: number | null;
+  maxJitterUs: number;
+  steps: Array<StepTiming>;
+};
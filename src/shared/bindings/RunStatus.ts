@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RunOutcome } from "./RunOutcome";
+
+/**
+ * Estado de una ejecución pedida desde la UI o por un disparador.
+ */
+export type RunStatus =
+  | { state: "queued" }
+  | { state: "running" }
+  | { state: "finished"; outcome: RunOutcome; error: string | null };
@@ -1,7 +1,60 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Condition } from "./Condition";
+import type { OnTimeout } from "./OnTimeout";
+import type { Side } from "./Side";
+import type { Timing } from "./Timing";
+import type { VarValue } from "./VarValue";
+import type { WaitState } from "./WaitState";
 
 export type SequenceStep =
   | { type: "keydown"; id: number; key: string }
   | { type: "keyup"; id: number; key: string }
-  | { type: "delay"; id: number; ms: number };
-
+  | { type: "delay"; id: number; ms: Timing }
+  | {
+      type: "tap";
+      id: number;
+      key: string;
+      holdMs: Timing;
+      holdFrames: number | null;
+    }
+  | {
+      type: "chord";
+      id: number;
+      keys: Array<string>;
+      holdMs: Timing;
+      holdFrames: number | null;
+    }
+  | { type: "typetext"; id: number; text: string; perCharDelayMs: number }
+  | { type: "mousemove"; id: number; x: number; y: number; relative: boolean }
+  | { type: "mousedown"; id: number; button: string }
+  | { type: "mouseup"; id: number; button: string }
+  | { type: "mouseclick"; id: number; button: string }
+  | { type: "scroll"; id: number; dx: number; dy: number }
+  | {
+      type: "waitforkey";
+      id: number;
+      key: string;
+      state: WaitState;
+      timeoutMs: number;
+      onTimeout: OnTimeout;
+    }
+  | { type: "callmacro"; id: number; macroId: number }
+  | { type: "repeat"; id: number; count: number; body: Array<SequenceStep> }
+  | { type: "loopuntilreleased"; id: number; body: Array<SequenceStep> }
+  | {
+      type: "if";
+      id: number;
+      condition: Condition;
+      then: Array<SequenceStep>;
+      else: Array<SequenceStep>;
+    }
+  | { type: "setfacing"; id: number; side: Side }
+  | { type: "setvar"; id: number; name: string; value: VarValue }
+  | {
+      type: "incrementvar";
+      id: number;
+      name: string;
+      by: number;
+      wrap: number | null;
+    }
+  | { type: "script"; id: number; source: string };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Macro } from "./Macro";
+import type { Profile } from "./Profile";
+
+export type SharedContent =
+  | { type: "profile"; data: Profile }
+  | { type: "macro"; data: Macro };
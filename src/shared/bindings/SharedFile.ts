@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Macro } from "./Macro";
+import type { SharedContent } from "./SharedContent";
+
+/**
+ * Archivo autocontenido para compartir un perfil o una macro.
+ */
+export type SharedFile = {
+  format: string;
+  version: number;
+  content: SharedContent;
+  /**
+   * Macros que llaman los pasos de `content` con `CallMacro`, directa o
+   * indirectamente. Al importar se crean en la biblioteca.
+   */
+  library: Array<Macro>;
+};
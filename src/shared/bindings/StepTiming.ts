@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Tiempos de un paso, en microsegundos desde el inicio de la ejecución.
+ */
+export type StepTiming = {
+  stepId: number;
+  depth: number;
+  /**
+   * Cuándo debía empezar según las esperas previstas.
+   */
+  intendedUs: number;
+  actualUs: number;
+  durationUs: number;
+};
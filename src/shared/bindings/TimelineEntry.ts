@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OutputEvent } from "./OutputEvent";
+
+export type TimelineEntry = {
+  /**
+   * Microsegundos desde el inicio, en tiempo virtual.
+   */
+  atUs: number;
+  event: OutputEvent;
+};
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Duración en milisegundos, fija o aleatoria para que la ejecución no sea
+ * siempre idéntica. Un número suelto es una duración fija.
+ */
+export type Timing =
+  | number
+  | { mean: number; stddev: number; min: number | null; max: number | null }
+  | { min: number; max: number };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { KeyCombination } from "./KeyCombination";
+
+/**
+ * Macro importada cuyo disparador ya usa otra macro del perfil destino.
+ */
+export type TriggerConflict = {
+  importedMacroId: number;
+  existingMacroId: number;
+  trigger: KeyCombination;
+};
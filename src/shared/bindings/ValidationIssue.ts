@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import { Nullable } from "@/shared/types/utils";
+
+/**
+ * Problema en la configuración que impediría ejecutar una macro.
+ */
+export type ValidationIssue = {
+  macroId: number;
+  /**
+   * Paso concreto que causa el problema, si lo hay.
+   */
+  stepId: Nullable<number>;
+  message: string;
+};